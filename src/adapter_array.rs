@@ -1,107 +1,131 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_lines, parse_usize, Command};
+use crate::{
+    default_sub_command, parse_usize, preset_arg, value_or_preset, GenerateOptions, InputReader,
+    ParseError, Puzzle, PRESET,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
-use simple_error::SimpleError;
+use clap::{App, Arg, ArgMatches};
+use nom::combinator::all_consuming;
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const ADAPTER_ARRAY: Command = Command::new(sub_command, "adapter-array", run);
+pub struct AdapterArray;
 
-#[derive(Debug)]
-struct AdapterArrayArgs {
-    file: String,
-    stat: JoltageStat,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdapterArrayArgs {
+    pub file: String,
+    pub stat: JoltageStat,
 }
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+#[derive(Debug, Clone, EnumString, EnumVariantNames, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab_case")]
-enum JoltageStat {
+#[serde(rename_all = "kebab-case")]
+pub enum JoltageStat {
     SumOfOneAndThreeJoltageGaps,
     CombinationOfValidAdapterChains,
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &ADAPTER_ARRAY,
-        "Takes a file with one number on each line and find joltage stats",
-        "Path to the input file. Each line contains one integer.",
-    )
-    .arg(
-        Arg::with_name("stat")
-            .short("s")
-            .help("Joltage stats requested. The stats available are as follows:\n\n\
-            sum-of-one-and-three-joltage-gaps: Finds the value of 1 joltage jumps and 3 joltage \
-            jumps using all adapters and sums them.\n\n\
-            combination-of-valid-adapter-chains: Finds the number of valid adapter combinations \
-            that could power the device.\n")
-            .takes_value(true)
-            .possible_values(&JoltageStat::VARIANTS)
-            .required(true),
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about(
-                "Finds the value of 1 joltage jumps and 3 joltage jumps using all adapters and sums them \
-                with the default input.",
-            )
-            .version("1.0.0"),
-    )
-    .subcommand(
-        SubCommand::with_name("part2")
-            .about(
-                "Finds the number of valid adapter combinations that could power the device \
-                with the default input.",
-            )
-            .version("1.0.0"),
-    )
-}
+impl Puzzle for AdapterArray {
+    type Args = AdapterArrayArgs;
+    type Input = Vec<usize>;
+    type Answer = usize;
+
+    const NAME: &'static str = "adapter-array";
+    const DAY: usize = 10;
+    const GENERATE_ABOUT: &'static str =
+        "Writes size adapters joined by gaps of 1 or 3 jolts, with at most four gaps of 1 in a row. \
+        Long runs stop once the number of arrangements would overflow.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Takes a file with one number on each line and find joltage stats",
+            "Path to the input file. Each line contains one integer.",
+        )
+        .arg(
+            Arg::with_name("stat")
+                .short("s")
+                .help("Joltage stats requested. The stats available are as follows:\n\n\
+                sum-of-one-and-three-joltage-gaps: Finds the value of 1 joltage jumps and 3 joltage \
+                jumps using all adapters and sums them.\n\n\
+                combination-of-valid-adapter-chains: Finds the number of valid adapter combinations \
+                that could power the device.\n")
+                .takes_value(true)
+                .possible_values(JoltageStat::VARIANTS)
+                .required_unless(PRESET),
+        )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds the value of 1 joltage jumps and 3 joltage jumps using all adapters \
+            and sums them with the default input.\n\n\
+            part2: Finds the number of valid adapter combinations that could power the \
+            device with the default input.\n",
+        ))
+    }
+
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&AdapterArrayArgs>,
+    ) -> Result<AdapterArrayArgs, Error> {
+        Ok(AdapterArrayArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            stat: value_or_preset(arguments, "stat", preset.map(|preset| &preset.stat))?,
+        })
+    }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let adapter_array_arguments = match arguments.subcommand_name() {
-        Some("part1") => AdapterArrayArgs {
-            file: "day10/input.txt".to_string(),
+    fn part1_args(&self, file: String) -> AdapterArrayArgs {
+        AdapterArrayArgs {
+            file,
             stat: JoltageStat::SumOfOneAndThreeJoltageGaps,
-        },
-        Some("part2") => AdapterArrayArgs {
-            file: "day10/input.txt".to_string(),
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<AdapterArrayArgs> {
+        Some(AdapterArrayArgs {
+            file,
             stat: JoltageStat::CombinationOfValidAdapterChains,
-        },
-        _ => AdapterArrayArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            stat: value_t_or_exit!(arguments.value_of("stat"), JoltageStat),
-        },
-    };
-
-    process_adapters(&adapter_array_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
         })
-        .map(|_| ())
-}
+    }
 
-fn process_adapters(adapter_array_arguments: &AdapterArrayArgs) -> Result<usize, Error> {
-    file_to_lines(&adapter_array_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_adapters))
-        .map(|mut adapters| {
-            adapters.push(0usize);
-            let max = (*adapters
-                .iter()
-                .fold_first(|max, adapter| if max > adapter { max } else { adapter })
-                .unwrap())
-            .clone();
-            adapters.push(max + 3);
-            adapters.sort();
-            adapters
-        })
-        .map(|adapters| match adapter_array_arguments.stat {
+    fn file<'a>(&self, arguments: &'a AdapterArrayArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: AdapterArrayArgs, file: String) -> AdapterArrayArgs {
+        AdapterArrayArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &AdapterArrayArgs,
+    ) -> Result<Vec<usize>, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(&self, adapters: Vec<usize>, arguments: &AdapterArrayArgs) -> Result<usize, Error> {
+        Ok(match arguments.stat {
             JoltageStat::SumOfOneAndThreeJoltageGaps => find_and_sum_1_and_3_votage_gaps(&adapters),
             JoltageStat::CombinationOfValidAdapterChains => {
                 find_number_of_unique_valid_adapter_combinations(&adapters)
             }
         })
+    }
 }
 
-fn find_and_sum_1_and_3_votage_gaps(adapters: &Vec<usize>) -> usize {
+pub fn find_and_sum_1_and_3_votage_gaps(adapters: &[usize]) -> usize {
     let ones = adapters
         .windows(2)
         .filter(|window| window[1] - window[0] == 1usize)
@@ -114,7 +138,7 @@ fn find_and_sum_1_and_3_votage_gaps(adapters: &Vec<usize>) -> usize {
     ones * threes
 }
 
-fn find_number_of_unique_valid_adapter_combinations(adapters: &Vec<usize>) -> usize {
+pub fn find_number_of_unique_valid_adapter_combinations(adapters: &[usize]) -> usize {
     let mut number_of_ones = 0usize;
     let mut counting_ones = false;
     let mut combinations = 1usize;
@@ -142,8 +166,84 @@ fn number_of_ways_consecutives_ones_can_be_arranged(n: usize) -> usize {
     (n * n - n + 2) / 2
 }
 
-fn parse_adapters(line: &String) -> Result<usize, Error> {
-    parse_usize(line)
-        .map_err(|_| SimpleError::new("Parse Error").into())
+pub fn parse_input(input: &mut InputReader) -> Result<Vec<usize>, Error> {
+    input.parse_lines(parse_adapters).map(|mut adapters| {
+        adapters.push(0usize);
+        let max = *adapters.iter().max().unwrap();
+        adapters.push(max + 3);
+        adapters.sort_unstable();
+        adapters
+    })
+}
+
+fn parse_adapters(line: &str) -> Result<usize, Error> {
+    all_consuming(parse_usize)(line)
+        .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+            ParseError::from_nom(line, err, "an unsigned integer").into()
+        })
         .map(|(_, number)| number)
 }
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    let mut joltage = 0;
+    let mut ones = 0;
+    let mut combinations = 1usize;
+
+    let mut adapters = (0..options.size)
+        .map(|_| {
+            let can_extend = ones < 4
+                && combinations
+                    .checked_mul(number_of_ways_consecutives_ones_can_be_arranged(ones + 1))
+                    .is_some();
+            if can_extend && rng.gen_bool(0.7) {
+                ones += 1;
+                joltage += 1;
+            } else {
+                combinations *= number_of_ways_consecutives_ones_can_be_arranged(ones);
+                ones = 0;
+                joltage += 3;
+            }
+            joltage
+        })
+        .collect::<Vec<usize>>();
+    adapters.shuffle(rng);
+    adapters
+        .iter()
+        .try_for_each(|adapter| writeln!(output, "{}", adapter))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_input;
+
+    #[test]
+    fn parse_input_adds_outlet_and_device() {
+        assert_eq!(
+            parse_input(&mut InputReader::from_text("4\n1\n")).unwrap(),
+            vec![0, 1, 4, 7]
+        );
+        assert!(parse_adapters("-1").is_err());
+    }
+
+    #[test]
+    fn solves_samples() {
+        let adapters = |name| parse_input(&mut sample_input(10, name)).unwrap();
+
+        assert_eq!(find_and_sum_1_and_3_votage_gaps(&adapters("sample")), 35);
+        assert_eq!(find_and_sum_1_and_3_votage_gaps(&adapters("sample2")), 220);
+        assert_eq!(
+            find_number_of_unique_valid_adapter_combinations(&adapters("sample")),
+            8
+        );
+        assert_eq!(
+            find_number_of_unique_valid_adapter_combinations(&adapters("sample2")),
+            19208
+        );
+    }
+}
@@ -1,37 +1,48 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_lines, Command};
+use crate::{
+    default_sub_command, preset_arg, value_or_preset, GenerateOptions, InputReader, ParseError,
+    Puzzle, PRESET,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches};
 use nom::{
     branch::alt,
     bytes::complete::take,
     character::complete,
-    combinator::{map, map_parser, map_res},
+    combinator::{all_consuming, map, map_parser, map_res},
     multi::fold_many1,
     sequence::tuple,
 };
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
 use simple_error::SimpleError;
+use std::fmt;
+use std::io::Write;
+use std::iter::FromIterator;
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const BINARY_BOARDING: Command = Command::new(sub_command, "binary-boarding", run);
+pub struct BinaryBoarding;
+
+const SEATS: usize = 1024;
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+#[derive(Debug, Clone, EnumString, EnumVariantNames, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab_case")]
-enum BoardingIdStategy {
+#[serde(rename_all = "kebab-case")]
+pub enum BoardingIdStategy {
     HighestInList,
     MissingFromList,
 }
 
-#[derive(Debug)]
-struct BinaryBoardingArgs {
-    file: String,
-    strategy: BoardingIdStategy,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinaryBoardingArgs {
+    pub file: String,
+    pub strategy: BoardingIdStategy,
 }
 
-#[derive(Debug)]
-struct BoardingPass {
-    row: usize,
-    column: usize,
+#[derive(Debug, Clone)]
+pub struct BoardingPass {
+    pub row: usize,
+    pub column: usize,
 }
 
 impl BoardingPass {
@@ -40,95 +51,173 @@ impl BoardingPass {
     }
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(&BINARY_BOARDING, "Takes a file with boarding passes and finds the highest seat id", "Path to the input file. Input should be newline separated boarding passes.")
-        .arg(
-            Arg::with_name("strategy")
-                .short("s")
-                .help(
-                    "What strategy to use when finding the boarding id.\n\n\
-                highest-in-list: Finds the highest boarding id in the list\n\
-                missing-from-list: searching for the missing boarding id or 0 if many missing ids\n",
-                )
-                .takes_value(true)
-                .possible_values(&BoardingIdStategy::VARIANTS)
-                .required(true),
-        )
-        .subcommand(
-            SubCommand::with_name("part1")
-                .about("Finds the highest boarding id from the default input")
-                .version("1.0.0"),
-        )
-        .subcommand(
-            SubCommand::with_name("part2")
-                .about("Finds the missing boarding id from the default input")
-                .version("1.0.0"),
-        )
-}
-
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let binary_boarding_arguments = match arguments.subcommand_name() {
-        Some("part1") => BinaryBoardingArgs {
-            file: "day5/input.txt".to_string(),
+/// Which seats have a boarding pass. Seat ids only go up to 1023, so this stays the same size
+/// however many passes are read.
+#[derive(Debug, Clone)]
+pub struct SeatMap {
+    taken: Vec<bool>,
+}
+
+impl SeatMap {
+    pub fn new() -> SeatMap {
+        SeatMap {
+            taken: vec![false; SEATS],
+        }
+    }
+
+    fn take(mut self, boarding_pass: BoardingPass) -> SeatMap {
+        self.taken[boarding_pass.seat_id()] = true;
+        self
+    }
+}
+
+impl Default for SeatMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromIterator<BoardingPass> for SeatMap {
+    fn from_iter<I: IntoIterator<Item = BoardingPass>>(boarding_passes: I) -> SeatMap {
+        boarding_passes
+            .into_iter()
+            .fold(SeatMap::new(), SeatMap::take)
+    }
+}
+
+impl fmt::Display for BoardingPass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (0..7)
+            .rev()
+            .map(|bit| if self.row >> bit & 1 == 1 { 'B' } else { 'F' })
+            .chain((0..3).rev().map(|bit| {
+                if self.column >> bit & 1 == 1 {
+                    'R'
+                } else {
+                    'L'
+                }
+            }))
+            .try_for_each(|character| write!(f, "{}", character))
+    }
+}
+
+impl Puzzle for BinaryBoarding {
+    type Args = BinaryBoardingArgs;
+    type Input = SeatMap;
+    type Answer = usize;
+
+    const NAME: &'static str = "binary-boarding";
+    const DAY: usize = 5;
+    const GENERATE_ABOUT: &'static str =
+        "Writes size boarding passes for consecutive seats with exactly one seat missing. \
+        Size must be between 2 and 1023.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(Self::NAME, "Takes a file with boarding passes and finds the highest seat id", "Path to the input file. Input should be newline separated boarding passes.")
+            .arg(
+                Arg::with_name("strategy")
+                    .short("s")
+                    .help(
+                        "What strategy to use when finding the boarding id.\n\n\
+                    highest-in-list: Finds the highest boarding id in the list\n\
+                    missing-from-list: Finds the seat missing between two taken seats, an error if there is none\n",
+                    )
+                    .takes_value(true)
+                    .possible_values(BoardingIdStategy::VARIANTS)
+                    .required_unless(PRESET),
+            )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds the highest boarding id from the default input\n\n\
+            part2: Finds the missing boarding id from the default input\n",
+        ))
+    }
+
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&BinaryBoardingArgs>,
+    ) -> Result<BinaryBoardingArgs, Error> {
+        Ok(BinaryBoardingArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            strategy: value_or_preset(
+                arguments,
+                "strategy",
+                preset.map(|preset| &preset.strategy),
+            )?,
+        })
+    }
+
+    fn part1_args(&self, file: String) -> BinaryBoardingArgs {
+        BinaryBoardingArgs {
+            file,
             strategy: BoardingIdStategy::HighestInList,
-        },
-        Some("part2") => BinaryBoardingArgs {
-            file: "day5/input.txt".to_string(),
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<BinaryBoardingArgs> {
+        Some(BinaryBoardingArgs {
+            file,
             strategy: BoardingIdStategy::MissingFromList,
-        },
-        _ => BinaryBoardingArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            strategy: value_t_or_exit!(arguments.value_of("strategy"), BoardingIdStategy),
-        },
-    };
-
-    process_boarding_passes(&binary_boarding_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
         })
-        .map(|_| ())
-}
+    }
 
-fn process_boarding_passes(binary_boarding_arguments: &BinaryBoardingArgs) -> Result<usize, Error> {
-    file_to_lines(&binary_boarding_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_boarding_pass_line))
-        .map(|boarding_passes| match binary_boarding_arguments.strategy {
-            BoardingIdStategy::HighestInList => find_highest_boarding_id(boarding_passes),
-            BoardingIdStategy::MissingFromList => find_missing_boarding_id(boarding_passes),
-        })
-}
+    fn file<'a>(&self, arguments: &'a BinaryBoardingArgs) -> &'a str {
+        &arguments.file
+    }
 
-fn find_highest_boarding_id(boarding_passes: Vec<BoardingPass>) -> usize {
-    boarding_passes.into_iter().fold(0, |max, value| {
-        if max > value.seat_id() {
-            max
-        } else {
-            value.seat_id()
+    fn with_file(&self, arguments: BinaryBoardingArgs, file: String) -> BinaryBoardingArgs {
+        BinaryBoardingArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &BinaryBoardingArgs,
+    ) -> Result<SeatMap, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(&self, seats: SeatMap, arguments: &BinaryBoardingArgs) -> Result<usize, Error> {
+        match arguments.strategy {
+            BoardingIdStategy::HighestInList => Ok(find_highest_boarding_id(&seats)),
+            BoardingIdStategy::MissingFromList => find_missing_boarding_id(&seats),
         }
-    })
+    }
 }
 
-fn find_missing_boarding_id(boarding_passes: Vec<BoardingPass>) -> usize {
-    let mut boarding_ids: Vec<usize> = boarding_passes
-        .into_iter()
-        .map(|boarding_pass| boarding_pass.seat_id())
-        .collect();
+pub fn find_highest_boarding_id(seats: &SeatMap) -> usize {
+    seats.taken.iter().rposition(|taken| *taken).unwrap_or(0)
+}
 
-    boarding_ids.sort();
-    boarding_ids
-        .windows(2)
-        .map(|window| (window[0], window[1]))
-        .find(|(low, high)| (low + 2) == *high)
-        .map(|(low, _)| low + 1)
-        .unwrap_or(0)
+pub fn find_missing_boarding_id(seats: &SeatMap) -> Result<usize, Error> {
+    seats
+        .taken
+        .windows(3)
+        .position(|window| window[0] && !window[1] && window[2])
+        .map(|low| low + 1)
+        .ok_or_else(|| SimpleError::new("No seat is missing between two taken seats").into())
 }
 
-fn parse_boarding_pass_line(line: &String) -> Result<BoardingPass, Error> {
-    tuple((
+pub fn parse_input(input: &mut InputReader) -> Result<SeatMap, Error> {
+    input.fold_lines(SeatMap::new(), parse_boarding_pass_line, SeatMap::take)
+}
+
+fn parse_boarding_pass_line(line: &str) -> Result<BoardingPass, Error> {
+    all_consuming(tuple((
         map_res(
             map_parser(
                 take(7usize),
-                fold_many1(
+                all_consuming(fold_many1(
                     alt((
                         map(complete::char('F'), |_| "0"),
                         map(complete::char('B'), |_| "1"),
@@ -138,14 +227,14 @@ fn parse_boarding_pass_line(line: &String) -> Result<BoardingPass, Error> {
                         acc.push_str(digit);
                         acc
                     },
-                ),
+                )),
             ),
             |result| usize::from_str_radix(&result, 2),
         ),
         map_res(
             map_parser(
                 take(3usize),
-                fold_many1(
+                all_consuming(fold_many1(
                     alt((
                         map(complete::char('L'), |_| "0"),
                         map(complete::char('R'), |_| "1"),
@@ -155,14 +244,99 @@ fn parse_boarding_pass_line(line: &String) -> Result<BoardingPass, Error> {
                         acc.push_str(digit);
                         acc
                     },
-                ),
+                )),
             ),
             |result| usize::from_str_radix(&result, 2),
         ),
-    ))(line.as_str())
-    .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse Error").into())
-    .map(|(_, (row, column))| BoardingPass {
-        row: row,
-        column: column,
+    )))(line)
+    .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+        ParseError::from_nom(line, err, "7 of 'F' or 'B' followed by 3 of 'L' or 'R'").into()
     })
+    .map(|(_, (row, column))| BoardingPass { row, column })
+}
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    options.require_size(2, 1023)?;
+    let first = rng.gen_range(0..=1023 - options.size);
+    let missing = first + rng.gen_range(1..options.size);
+
+    let mut boarding_passes = (first..=first + options.size)
+        .filter(|seat_id| *seat_id != missing)
+        .map(|seat_id| BoardingPass {
+            row: seat_id / 8,
+            column: seat_id % 8,
+        })
+        .collect::<Vec<BoardingPass>>();
+    boarding_passes.shuffle(rng);
+    boarding_passes
+        .iter()
+        .try_for_each(|boarding_pass| writeln!(output, "{}", boarding_pass))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generated_input, sample_input};
+    use proptest::prelude::*;
+
+    #[test]
+    fn parses_boarding_pass_line() {
+        let boarding_pass = parse_boarding_pass_line("FBFBBFFRLR").unwrap();
+
+        assert_eq!((boarding_pass.row, boarding_pass.column), (44, 5));
+        assert_eq!(boarding_pass.seat_id(), 357);
+    }
+
+    #[test]
+    fn rejects_invalid_boarding_pass_line() {
+        assert!(parse_boarding_pass_line("FBFBBFFRLX").is_err());
+        assert!(parse_boarding_pass_line("FBFBBFRLR").is_err());
+    }
+
+    #[test]
+    fn finds_highest_boarding_id_in_sample() {
+        let seats = parse_input(&mut sample_input(5, "sample")).unwrap();
+
+        assert_eq!(find_highest_boarding_id(&seats), 820);
+    }
+
+    #[test]
+    fn finds_missing_boarding_id() {
+        let seats = [10, 11, 13, 14]
+            .iter()
+            .map(|seat_id| BoardingPass {
+                row: seat_id / 8,
+                column: seat_id % 8,
+            })
+            .collect();
+
+        assert_eq!(find_missing_boarding_id(&seats).unwrap(), 12);
+        assert!(find_missing_boarding_id(&SeatMap::new()).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn boarding_pass_round_trips(row in 0usize..128, column in 0usize..8) {
+            let code = BoardingPass { row, column }.to_string();
+            let boarding_pass = parse_boarding_pass_line(&code).unwrap();
+
+            prop_assert_eq!((boarding_pass.row, boarding_pass.column), (row, column));
+        }
+    }
+
+    #[test]
+    fn generated_passes_miss_exactly_one_seat() {
+        let input = generated_input(generate_input, 300, 2);
+        let seats = parse_input(&mut InputReader::from_text(&input)).unwrap();
+        let highest = find_highest_boarding_id(&seats);
+        let missing = find_missing_boarding_id(&seats).unwrap();
+
+        assert_eq!(seats.taken.iter().filter(|taken| **taken).count(), 300);
+        assert!(highest - 300 < missing && missing < highest);
+    }
 }
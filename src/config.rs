@@ -0,0 +1,180 @@
+use anyhow::Error;
+use serde::Deserialize;
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_VAR: &str = "AOC_CONFIG";
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+const CONFIG_FILE: &str = "adventofcode2020/config.toml";
+pub const DEFAULT_INPUT: &str = "input";
+const INPUT_EXTENSION: &str = "txt";
+const ANSWERS_FILE: &str = "answers.txt";
+
+/// Where puzzle inputs live. Loaded from `$AOC_CONFIG`, or
+/// `$XDG_CONFIG_HOME/adventofcode2020/config.toml` (falling back to `~/.config`), e.g.
+///
+/// ```toml
+/// input_dir = "/home/me/aoc/2020"  # holds day1/, day2/, ...
+///
+/// [days]
+/// 11 = "/home/me/seating"          # holds day 11's input.txt, sample.txt, ...
+/// ```
+///
+/// `AOC_INPUT_DIR` overrides `input_dir`. Relative paths are relative to the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub input_dir: Option<PathBuf>,
+
+    #[serde(default)]
+    pub days: HashMap<String, PathBuf>,
+}
+
+impl Config {
+    pub fn load() -> Result<Config, Error> {
+        let config = match env::var_os(CONFIG_VAR) {
+            Some(path) => Config::from_file(Path::new(&path))?,
+            None => match user_config_path().filter(|path| path.exists()) {
+                Some(path) => Config::from_file(&path)?,
+                None => Config::default(),
+            },
+        };
+
+        Ok(
+            match env::var_os(INPUT_DIR_VAR).filter(|dir| !dir.is_empty()) {
+                Some(dir) => Config {
+                    input_dir: Some(PathBuf::from(dir)),
+                    ..config
+                },
+                None => config,
+            },
+        )
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, Error> {
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        fs::read_to_string(path)
+            .map_err(|err| Error::from(err).context(format!("Could not read {}", path.display())))
+            .and_then(|text| {
+                toml::from_str::<Config>(&text)
+                    .map_err(|err| SimpleError::new(format!("{}: {}", path.display(), err)).into())
+            })
+            .map(|config| Config {
+                input_dir: config.input_dir.map(|dir| base.join(dir)),
+                days: config
+                    .days
+                    .into_iter()
+                    .map(|(day, dir)| (day, base.join(dir)))
+                    .collect(),
+            })
+    }
+
+    pub fn day_dir(&self, day: usize) -> PathBuf {
+        self.days
+            .get(&day.to_string())
+            .cloned()
+            .unwrap_or_else(|| match &self.input_dir {
+                Some(dir) => dir.join(format!("day{}", day)),
+                None => PathBuf::from(format!("day{}", day)),
+            })
+    }
+
+    pub fn input_file(&self, day: usize, name: &str) -> Result<String, Error> {
+        let dir = self.day_dir(day);
+        let file = dir.join(name);
+        let file = match file.extension() {
+            Some(_) => file,
+            None => file.with_extension(INPUT_EXTENSION),
+        };
+
+        if file.is_file() {
+            Ok(file.to_string_lossy().into_owned())
+        } else {
+            Err(SimpleError::new(format!(
+                "No input named {} in {}, available inputs: {}",
+                name,
+                dir.display(),
+                self.input_names(day).join(", ")
+            ))
+            .into())
+        }
+    }
+
+    pub fn input_names(&self, day: usize) -> Vec<String> {
+        let mut names = fs::read_dir(self.day_dir(day))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        path.extension()
+                            .is_some_and(|extension| extension == INPUT_EXTENSION)
+                            && path.file_name().is_some_and(|name| name != ANSWERS_FILE)
+                    })
+                    .filter_map(|path| {
+                        path.file_stem()
+                            .map(|stem| stem.to_string_lossy().into_owned())
+                    })
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    pub fn answers_file(&self, day: usize) -> PathBuf {
+        self.day_dir(day).join(ANSWERS_FILE)
+    }
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join(CONFIG_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_dir_prefers_per_day_entries() {
+        let config =
+            toml::from_str::<Config>("input_dir = \"inputs\"\n[days]\n11 = \"seats\"\n").unwrap();
+
+        assert_eq!(config.day_dir(3), Path::new("inputs/day3"));
+        assert_eq!(config.day_dir(11), Path::new("seats"));
+        assert_eq!(Config::default().day_dir(3), Path::new("day3"));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>("inputs = \"somewhere\"\n").is_err());
+    }
+
+    #[test]
+    fn input_file_lists_available_inputs_when_missing() {
+        let config = Config {
+            input_dir: Some(PathBuf::from(env!("CARGO_MANIFEST_DIR"))),
+            days: HashMap::new(),
+        };
+
+        assert!(config
+            .input_file(10, "sample2")
+            .unwrap()
+            .ends_with("day10/sample2.txt"));
+        assert_eq!(
+            config.input_file(10, "missing").unwrap_err().to_string(),
+            format!(
+                "No input named missing in {}/day10, available inputs: input, sample, sample2",
+                env!("CARGO_MANIFEST_DIR")
+            )
+        );
+    }
+}
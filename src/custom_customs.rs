@@ -1,152 +1,241 @@
-use crate::lib::{default_sub_command, file_to_string, Command};
-use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
-use nom::{
-    branch::alt,
-    bytes::complete::{tag, take, take_till1, take_until},
-    combinator::{map, map_parser},
-    multi::{fold_many1, separated_list0, separated_list1},
+use crate::{
+    default_sub_command, parse_lines, preset_arg, random_letter, value_or_preset, GenerateOptions,
+    InputReader, ParseError, Puzzle, PRESET,
 };
-use simple_error::SimpleError;
-use std::collections::HashSet;
+use anyhow::Error;
+use clap::{App, Arg, ArgMatches};
+use nom::{bytes::complete::take_while1, combinator::all_consuming};
+use rand::{seq::index, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::io::Write;
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const CUSTOM_CUSTOMS: Command = Command::new(sub_command, "custom-customs", run);
+pub struct CustomCustoms;
 
-#[derive(Debug)]
-struct CustomCustomsArgs {
-    file: String,
-    strategy: CustomsCountStrategy,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomCustomsArgs {
+    pub file: String,
+    pub strategy: CustomsCountStrategy,
 }
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+/// Each strategy's sum over the groups, added up as the groups are read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnswerCounts {
+    pub unique_per_group: usize,
+    pub intersection_per_group: usize,
+}
+
+#[derive(Debug, Clone, EnumString, EnumVariantNames, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab_case")]
-enum CustomsCountStrategy {
+#[serde(rename_all = "kebab-case")]
+pub enum CustomsCountStrategy {
     CountUniquePerGroup,
     CountIntersectionPerGroup,
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(&CUSTOM_CUSTOMS, "Takes a file with customs questions groups and cacluates the sum of unique per group questions", "Path to the input file. Groups are separated by a blank line, people within a group are \
-    separated by a newline.")
-        .arg(
-            Arg::with_name("strategy")
-                .short("s")
-                .help("Counting strategy for each group. The strategies are as follows:\n\n\
-                count-unique-per-group: Counts the number of unqiue anwers within a group\n\n\
-                count-intersection-per-group: Count the number of questions that all members \
-                of a group answered.\n")
-                .takes_value(true)
-                .possible_values(&CustomsCountStrategy::VARIANTS)
-                .required(true),
-        )
-        .subcommand(
-            SubCommand::with_name("part1")
-                .about("Finds the sum of unique group answers with the default input")
-                .version("1.0.0"),
-        )
-        .subcommand(
-            SubCommand::with_name("part2")
-                .about("Finds the sum of answers all group members completed with the default input")
-                .version("1.0.0"),
-        )
-}
+impl Puzzle for CustomCustoms {
+    type Args = CustomCustomsArgs;
+    type Input = AnswerCounts;
+    type Answer = usize;
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let custom_customs_arguments = match arguments.subcommand_name() {
-        Some("part1") => CustomCustomsArgs {
-            file: "day6/input.txt".to_string(),
+    const NAME: &'static str = "custom-customs";
+    const DAY: usize = 6;
+    const GENERATE_ABOUT: &'static str =
+        "Writes size groups of one to five people. Everyone in a group shares a few answers.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(Self::NAME, "Takes a file with customs questions groups and cacluates the sum of unique per group questions", "Path to the input file. Groups are separated by a blank line, people within a group are \
+        separated by a newline.")
+            .arg(
+                Arg::with_name("strategy")
+                    .short("s")
+                    .help("Counting strategy for each group. The strategies are as follows:\n\n\
+                    count-unique-per-group: Counts the number of unqiue anwers within a group\n\n\
+                    count-intersection-per-group: Count the number of questions that all members \
+                    of a group answered.\n")
+                    .takes_value(true)
+                    .possible_values(CustomsCountStrategy::VARIANTS)
+                    .required_unless(PRESET),
+            )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds the sum of unique group answers with the default input\n\n\
+            part2: Finds the sum of answers all group members completed with the default \
+            input\n",
+        ))
+    }
+
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&CustomCustomsArgs>,
+    ) -> Result<CustomCustomsArgs, Error> {
+        Ok(CustomCustomsArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            strategy: value_or_preset(
+                arguments,
+                "strategy",
+                preset.map(|preset| &preset.strategy),
+            )?,
+        })
+    }
+
+    fn part1_args(&self, file: String) -> CustomCustomsArgs {
+        CustomCustomsArgs {
+            file,
             strategy: CustomsCountStrategy::CountUniquePerGroup,
-        },
-        Some("part2") => CustomCustomsArgs {
-            file: "day6/input.txt".to_string(),
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<CustomCustomsArgs> {
+        Some(CustomCustomsArgs {
+            file,
             strategy: CustomsCountStrategy::CountIntersectionPerGroup,
-        },
-        _ => CustomCustomsArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            strategy: value_t_or_exit!(arguments.value_of("strategy"), CustomsCountStrategy),
-        },
-    };
+        })
+    }
+
+    fn file<'a>(&self, arguments: &'a CustomCustomsArgs) -> &'a str {
+        &arguments.file
+    }
 
-    process_customs_forms(&custom_customs_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
+    fn with_file(&self, arguments: CustomCustomsArgs, file: String) -> CustomCustomsArgs {
+        CustomCustomsArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &CustomCustomsArgs,
+    ) -> Result<AnswerCounts, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(
+        &self,
+        answer_counts: AnswerCounts,
+        arguments: &CustomCustomsArgs,
+    ) -> Result<usize, Error> {
+        Ok(match arguments.strategy {
+            CustomsCountStrategy::CountUniquePerGroup => answer_counts.unique_per_group,
+            CustomsCountStrategy::CountIntersectionPerGroup => answer_counts.intersection_per_group,
         })
-        .map(|_| ())
+    }
 }
 
-fn process_customs_forms(custom_customs_arguments: &CustomCustomsArgs) -> Result<usize, Error> {
-    file_to_string(&custom_customs_arguments.file)
-        .and_then(|file| parse_customs_forms(&file))
-        .map(|customs_forms| match custom_customs_arguments.strategy {
-            CustomsCountStrategy::CountUniquePerGroup => {
-                count_unique_answers_per_group(customs_forms)
-            }
-            CustomsCountStrategy::CountIntersectionPerGroup => {
-                count_answers_all_group_members_answered(customs_forms)
-            }
+pub fn count_unique_answers(group: &[HashSet<char>]) -> usize {
+    group
+        .iter()
+        .fold(HashSet::new(), |mut acc: HashSet<char>, person| {
+            acc.extend(person);
+            acc
         })
+        .len()
 }
 
-fn count_unique_answers_per_group(customs_forms: Vec<Vec<HashSet<char>>>) -> usize {
-    customs_forms
-        .into_iter()
-        .map(|group| {
-            group
-                .into_iter()
-                .fold(HashSet::new(), |mut acc: HashSet<char>, person| {
-                    acc.extend(&person);
-                    acc
-                })
-                .len()
+pub fn count_answers_all_group_members_answered(group: &[HashSet<char>]) -> usize {
+    group
+        .iter()
+        .cloned()
+        .reduce(|acc: HashSet<char>, person| {
+            acc.into_iter()
+                .filter(|answer| person.contains(answer))
+                .collect()
         })
-        .fold(0, |acc, answers| acc + answers)
+        .map(|questions| questions.len())
+        .unwrap_or(0)
 }
 
-fn count_answers_all_group_members_answered(customs_forms: Vec<Vec<HashSet<char>>>) -> usize {
-    customs_forms
-        .into_iter()
-        .map(|group| {
-            group
-                .into_iter()
-                .fold_first(|acc: HashSet<char>, person| {
-                    acc.into_iter()
-                        .filter(|answer| person.contains(answer))
-                        .collect()
-                })
-                .map(|questions| questions.len())
-                .unwrap_or(0)
+pub fn parse_input(input: &mut InputReader) -> Result<AnswerCounts, Error> {
+    input.fold_records(
+        AnswerCounts::default(),
+        parse_group,
+        |answer_counts, group| AnswerCounts {
+            unique_per_group: answer_counts.unique_per_group + count_unique_answers(&group),
+            intersection_per_group: answer_counts.intersection_per_group
+                + count_answers_all_group_members_answered(&group),
+        },
+    )
+}
+
+fn parse_group(record: &str) -> Result<Vec<HashSet<char>>, Error> {
+    parse_lines(record.lines(), parse_answers)
+}
+
+fn parse_answers(line: &str) -> Result<HashSet<char>, Error> {
+    all_consuming(take_while1(|character: char| {
+        character.is_ascii_lowercase()
+    }))(line)
+    .map(|(_, answers)| answers.chars().collect())
+    .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+        ParseError::from_nom(line, err, "answers from a to z").into()
+    })
+}
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    (0..options.size).try_for_each(|group| {
+        let shared = random_answers(rng, 3);
+        let people = rng.gen_range(1..=5);
+
+        if group > 0 {
+            writeln!(output)?;
+        }
+        (0..people).try_for_each(|_| {
+            let mut answers = random_answers(rng, 5);
+            answers.extend(&shared);
+            if answers.is_empty() {
+                answers.insert(random_letter(rng));
+            }
+            writeln!(output, "{}", answers.into_iter().collect::<String>())
         })
-        .fold(0, |acc, answers| acc + answers)
+    })?;
+    Ok(())
+}
+
+fn random_answers(rng: &mut dyn RngCore, most: usize) -> BTreeSet<char> {
+    let count = rng.gen_range(0..=most);
+    index::sample(rng, 26, count)
+        .into_iter()
+        .map(|letter| (b'a' + letter as u8) as char)
+        .collect()
 }
 
-fn parse_customs_forms(file: &String) -> Result<Vec<Vec<HashSet<char>>>, Error> {
-    separated_list0(
-        tag("\n\n"),
-        map_parser(
-            alt((take_until("\n\n"), take_till1(|_| false))),
-            separated_list1(
-                tag("\n"),
-                map_parser(
-                    alt((take_until("\n"), take_till1(|_| false))),
-                    map(
-                        fold_many1(take(1usize), String::new(), |mut acc: String, item| {
-                            acc.push_str(item);
-                            acc
-                        }),
-                        |answers| {
-                            let mut unique_answers = HashSet::new();
-                            answers.chars().for_each(|character| {
-                                unique_answers.insert(character);
-                            });
-                            unique_answers
-                        },
-                    ),
-                ),
-            ),
-        ),
-    )(file.as_str())
-    .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse Error").into())
-    .map(|(_, custom_forms)| custom_forms)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_input;
+
+    #[test]
+    fn parses_group() {
+        let group = parse_group("ab\nac").unwrap();
+
+        assert_eq!(group.len(), 2);
+        assert!(group[1].contains(&'c'));
+        assert!(parse_group("ab\nA").is_err());
+    }
+
+    #[test]
+    fn solves_sample() {
+        assert_eq!(
+            parse_input(&mut sample_input(6, "sample")).unwrap(),
+            AnswerCounts {
+                unique_per_group: 11,
+                intersection_per_group: 6
+            }
+        );
+    }
 }
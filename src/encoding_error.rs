@@ -1,161 +1,302 @@
-use crate::lib::{
-    default_sub_command, file_to_lines, parse_isize, parse_lines, Command, SumChecker,
+use crate::{
+    default_sub_command, flag_or_preset, parse_isize, preset_arg, value_or_preset, GenerateOptions,
+    InputReader, ParseError, Puzzle, SumChecker, PRESET,
 };
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches};
+use nom::combinator::all_consuming;
+use rand::{seq::index, Rng, RngCore};
+use serde::{Deserialize, Serialize};
 use simple_error::SimpleError;
+use std::io::Write;
 
-pub const ENCODING_ERROR: Command = Command::new(sub_command, "encoding-error", run);
+const PREAMBLE_LENGTH: usize = 25;
 
-#[derive(Debug)]
-struct EncodingErrorArgs {
-    file: String,
-    preamble_length: usize,
-    exploit: bool,
-}
+pub struct EncodingError;
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &ENCODING_ERROR,
-        "Takes a file with one number on each line and a preamble find the number that does not fit the \
-        encoding.",
-        "Path to the input file. Each line contains one integer.",
-    )
-    .arg(
-        Arg::with_name("preamble")
-            .short("p")
-            .help("Length of the preamble for the XMAS protocol.")
-            .takes_value(true)
-            .required(true),
-    )
-    .arg(
-        Arg::with_name("exploit")
-        .short("e")
-        .help("If passed, finds the exploit number based on the number found that did not fit encoding.")
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about(
-                "Finds the value that does not fit then encoding using dfeault input.",
-            )
-            .version("1.0.0"),
-    )
-    .subcommand(
-        SubCommand::with_name("part2")
-            .about(
-                "Finds the value that does not fit then encoding using dfeault input. \
-                Then finds the exploit value.",
-            )
-            .version("1.0.0"),
-    )
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodingErrorArgs {
+    pub file: String,
+    pub preamble_length: usize,
+    pub exploit: bool,
 }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let encoding_error_arguments = match arguments.subcommand_name() {
-        Some("part1") => EncodingErrorArgs {
-            file: "day9/input.txt".to_string(),
-            preamble_length: 25,
+impl Puzzle for EncodingError {
+    type Args = EncodingErrorArgs;
+    type Input = Vec<isize>;
+    type Answer = isize;
+
+    const NAME: &'static str = "encoding-error";
+    const DAY: usize = 9;
+    const GENERATE_ABOUT: &'static str =
+        "Writes size numbers with a preamble of 25 and one number in the second half that does not \
+        fit. Size must be at least 28, and numbers overflow somewhere past a thousand lines.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Takes a file with one number on each line and a preamble find the number that does not fit the \
+            encoding.",
+            "Path to the input file. Each line contains one integer.",
+        )
+        .arg(
+            Arg::with_name("preamble")
+                .short("p")
+                .help("Length of the preamble for the XMAS protocol.")
+                .takes_value(true)
+                .required_unless(PRESET),
+        )
+        .arg(
+            Arg::with_name("exploit")
+            .short("e")
+            .help("If passed, finds the exploit number based on the number found that did not fit encoding.")
+        )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds the value that does not fit then encoding using dfeault input.\n\n\
+            part2: Finds the value that does not fit then encoding using dfeault input. Then \
+            finds the exploit value.\n",
+        ))
+    }
+
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&EncodingErrorArgs>,
+    ) -> Result<EncodingErrorArgs, Error> {
+        Ok(EncodingErrorArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            preamble_length: value_or_preset(
+                arguments,
+                "preamble",
+                preset.map(|preset| &preset.preamble_length),
+            )?,
+            exploit: flag_or_preset(arguments, "exploit", preset.map(|preset| &preset.exploit)),
+        })
+    }
+
+    fn part1_args(&self, file: String) -> EncodingErrorArgs {
+        EncodingErrorArgs {
+            file,
+            preamble_length: PREAMBLE_LENGTH,
             exploit: false,
-        },
-        Some("part2") => EncodingErrorArgs {
-            file: "day9/input.txt".to_string(),
-            preamble_length: 25,
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<EncodingErrorArgs> {
+        Some(EncodingErrorArgs {
+            file,
+            preamble_length: PREAMBLE_LENGTH,
             exploit: true,
-        },
-        _ => EncodingErrorArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            preamble_length: value_t_or_exit!(arguments.value_of("preamble"), usize),
-            exploit: arguments.is_present("exploit"),
-        },
-    };
-
-    process_numbers(&encoding_error_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
         })
-        .map(|_| ())
-}
+    }
 
-fn process_numbers(encoding_error_arguments: &EncodingErrorArgs) -> Result<isize, Error> {
-    file_to_lines(&encoding_error_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_numbers))
-        .map(|numbers| {
-            let result = find_missing_number(&numbers, &encoding_error_arguments.preamble_length);
-
-            if encoding_error_arguments.exploit {
-                let exploit_range =
-                    find_continous_sequence_of_at_least_two_that_sum_to_target(&result, &numbers);
-                let min = (&exploit_range)
-                    .into_iter()
-                    .fold(
-                        isize::MAX,
-                        |low, number| {
-                            if low < *number {
-                                low
-                            } else {
-                                *number
-                            }
-                        },
-                    );
-
-                let max = (&exploit_range)
-                    .into_iter()
-                    .fold(
-                        isize::MIN,
-                        |high, number| {
-                            if high > *number {
-                                high
-                            } else {
-                                *number
-                            }
-                        },
-                    );
-                min + max
-            } else {
-                result
-            }
-        })
+    fn file<'a>(&self, arguments: &'a EncodingErrorArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: EncodingErrorArgs, file: String) -> EncodingErrorArgs {
+        EncodingErrorArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &EncodingErrorArgs,
+    ) -> Result<Vec<isize>, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(&self, numbers: Vec<isize>, arguments: &EncodingErrorArgs) -> Result<isize, Error> {
+        let result = find_missing_number(&numbers, &arguments.preamble_length)?;
+
+        if arguments.exploit {
+            find_exploit(&result, &numbers)
+        } else {
+            Ok(result)
+        }
+    }
 }
 
-fn find_missing_number(numbers: &Vec<isize>, preamble_length: &usize) -> isize {
-    *numbers
+pub fn find_missing_number(numbers: &[isize], preamble_length: &usize) -> Result<isize, Error> {
+    numbers
         .windows(preamble_length + 1)
         .map(|window| window.split_last().unwrap())
         .map(|(test_number, preamble)| {
-            SumChecker::with_vec(&preamble.to_vec())
+            SumChecker::with_vec(preamble)
                 .find_sum_of_n(test_number, 2)
                 .map_err(|_| test_number)
         })
         .find_map(|result| result.err())
-        .unwrap_or(&0)
+        .copied()
+        .ok_or_else(|| {
+            SimpleError::new(format!(
+                "Every number is the sum of two of the {} before it",
+                preamble_length
+            ))
+            .into()
+        })
+}
+
+pub fn find_exploit(target: &isize, numbers: &[isize]) -> Result<isize, Error> {
+    let exploit_range =
+        find_continous_sequence_of_at_least_two_that_sum_to_target(target, numbers)?;
+    let min = exploit_range.iter().min().unwrap_or(&0);
+    let max = exploit_range.iter().max().unwrap_or(&0);
+
+    Ok(min + max)
 }
 
 fn find_continous_sequence_of_at_least_two_that_sum_to_target(
     target: &isize,
-    numbers: &Vec<isize>,
-) -> Vec<isize> {
+    numbers: &[isize],
+) -> Result<Vec<isize>, Error> {
     let mut low = 0;
-    let mut high = 1;
+    let mut high = 2;
 
-    loop {
+    while high <= numbers.len() {
         match sum_from_low_to_high(&low, &high, numbers) {
-            sum if sum > *target => low += 1,
-            sum if sum < *target => high += 1,
-            _ => break,
+            sum if sum == *target => return Ok(numbers[low..high].to_vec()),
+            sum if sum > *target && high - low > 2 => low += 1,
+            _ => high += 1,
         }
     }
 
-    numbers[low..high].to_vec()
+    Err(SimpleError::new(format!(
+        "No continuous run of at least two numbers sums to {}",
+        target
+    ))
+    .into())
 }
 
-fn sum_from_low_to_high(low: &usize, high: &usize, numbers: &Vec<isize>) -> isize {
-    numbers[*low..*high]
-        .iter()
-        .fold(0, |acc, number| acc + number)
+fn sum_from_low_to_high(low: &usize, high: &usize, numbers: &[isize]) -> isize {
+    numbers[*low..*high].iter().sum()
+}
+
+pub fn parse_input(input: &mut InputReader) -> Result<Vec<isize>, Error> {
+    input.parse_lines(parse_numbers)
 }
 
-fn parse_numbers(line: &String) -> Result<isize, Error> {
-    parse_isize(line)
-        .map_err(|_| SimpleError::new("Parse Error").into())
+fn parse_numbers(line: &str) -> Result<isize, Error> {
+    all_consuming(parse_isize)(line)
+        .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+            ParseError::from_nom(line, err, "an integer").into()
+        })
         .map(|(_, number)| number)
 }
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    options.require_size(PREAMBLE_LENGTH + 3, usize::MAX)?;
+    let invalid_at = rng.gen_range((PREAMBLE_LENGTH + 2).max(options.size / 2)..options.size);
+    let mut numbers = (0..PREAMBLE_LENGTH)
+        .map(|_| rng.gen_range(1..=50))
+        .collect::<Vec<isize>>();
+
+    (PREAMBLE_LENGTH..options.size).try_for_each(|line| {
+        let number = if line == invalid_at {
+            generate_invalid_number(&numbers, rng)?
+        } else {
+            // Summing the smallest numbers in the preamble keeps the growth as slow as possible
+            let mut preamble = numbers[line - PREAMBLE_LENGTH..].to_vec();
+            preamble.sort_unstable();
+            let pair = index::sample(rng, 3, 2);
+            preamble[pair.index(0)]
+                .checked_add(preamble[pair.index(1)])
+                .ok_or_else(|| {
+                    SimpleError::new(format!(
+                        "Numbers overflow after {} lines, try a smaller size",
+                        line
+                    ))
+                })?
+        };
+        numbers.push(number);
+        Ok::<(), Error>(())
+    })?;
+
+    numbers
+        .iter()
+        .try_for_each(|number| writeln!(output, "{}", number))?;
+    Ok(())
+}
+
+/// Sum of an earlier contiguous run that is not the sum of two numbers in the preamble. Like the
+/// real inputs, it doesn't appear anywhere earlier in the list.
+fn generate_invalid_number(numbers: &[isize], rng: &mut dyn RngCore) -> Result<isize, Error> {
+    let preamble = SumChecker::with_vec(&numbers[numbers.len() - PREAMBLE_LENGTH..]);
+    (0..100)
+        .filter_map(|_| {
+            let start = rng.gen_range(0..numbers.len() - 2);
+            let length = rng.gen_range(3..=(numbers.len() - start).min(6));
+            numbers[start..start + length]
+                .iter()
+                .try_fold(0isize, |sum, number| sum.checked_add(*number))
+        })
+        .find(|sum| !numbers.contains(sum) && preamble.find_sum_of_n(sum, 2).is_err())
+        .ok_or_else(|| {
+            SimpleError::new("Could not place an invalid number, try another seed").into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generated_input, sample_input};
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse_numbers("-15").unwrap(), -15);
+        assert!(parse_numbers("15a").is_err());
+    }
+
+    #[test]
+    fn solves_sample_with_preamble_of_five() {
+        let numbers = parse_input(&mut sample_input(9, "sample")).unwrap();
+        let missing_number = find_missing_number(&numbers, &5).unwrap();
+
+        assert_eq!(missing_number, 127);
+        assert_eq!(find_exploit(&missing_number, &numbers).unwrap(), 62);
+    }
+
+    #[test]
+    fn fails_when_no_run_sums_to_target() {
+        assert!(find_exploit(&100, &[1, 2, 3]).is_err());
+        assert!(find_exploit(&4, &[1, 2, 5, 10]).is_err());
+        assert!(find_exploit(&5, &[1, 2, 5]).is_err());
+    }
+
+    #[test]
+    fn fails_when_every_number_is_valid() {
+        assert!(find_missing_number(&[1, 2, 3, 5, 8], &2).is_err());
+    }
+
+    #[test]
+    fn generated_numbers_have_one_invalid_number_in_the_second_half() {
+        let numbers = parse_input(&mut InputReader::from_text(&generated_input(
+            generate_input,
+            200,
+            5,
+        )))
+        .unwrap();
+        let invalid = find_missing_number(&numbers, &PREAMBLE_LENGTH).unwrap();
+        let position = numbers
+            .iter()
+            .position(|number| *number == invalid)
+            .unwrap();
+
+        assert!(position >= 100);
+        assert!(find_exploit(&invalid, &numbers).unwrap() > 0);
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::Error;
+use simple_error::SimpleError;
+use std::ops::{Add, Mul, Sub};
+
+/// A position on an unbounded plane, x grows to the east and y grows to the north.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+/// A displacement on the same plane as Point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vector {
+    pub x: isize,
+    pub y: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
+const CLOCKWISE_HEADINGS: [Heading; 4] =
+    [Heading::North, Heading::East, Heading::South, Heading::West];
+
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+
+    pub fn new(x: isize, y: isize) -> Point {
+        Point { x, y }
+    }
+
+    pub fn manhattan_distance(&self, other: &Point) -> isize {
+        (*self - *other).manhattan_length()
+    }
+
+    pub fn euclidean_distance(&self, other: &Point) -> f64 {
+        (*self - *other).length()
+    }
+}
+
+impl Vector {
+    pub fn new(x: isize, y: isize) -> Vector {
+        Vector { x, y }
+    }
+
+    pub fn manhattan_length(&self) -> isize {
+        self.x.abs() + self.y.abs()
+    }
+
+    pub fn length(&self) -> f64 {
+        (self.x as f64).hypot(self.y as f64)
+    }
+
+    /// Rotates counterclockwise, use negative degrees to turn clockwise. Right angles are exact,
+    /// anything else is rounded to the nearest whole point.
+    pub fn rotate(&self, degrees: isize) -> Vector {
+        match degrees.rem_euclid(360) {
+            0 => *self,
+            90 => Vector::new(-self.y, self.x),
+            180 => Vector::new(-self.x, -self.y),
+            270 => Vector::new(self.y, -self.x),
+            degrees => {
+                let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+                let (x, y) = (self.x as f64, self.y as f64);
+                Vector::new(
+                    (x * cos - y * sin).round() as isize,
+                    (x * sin + y * cos).round() as isize,
+                )
+            }
+        }
+    }
+}
+
+impl Heading {
+    pub fn vector(&self) -> Vector {
+        match self {
+            Heading::North => Vector::new(0, 1),
+            Heading::East => Vector::new(1, 0),
+            Heading::South => Vector::new(0, -1),
+            Heading::West => Vector::new(-1, 0),
+        }
+    }
+
+    /// Rotates counterclockwise by whole quarter turns, use negative degrees to turn clockwise.
+    /// Anything other than a multiple of 90 degrees is an error.
+    pub fn rotate(&self, degrees: isize) -> Result<Heading, Error> {
+        if degrees % 90 != 0 {
+            return Err(SimpleError::new(format!(
+                "Headings only turn by multiples of 90 degrees, got {}",
+                degrees
+            ))
+            .into());
+        }
+        let index = *self as isize - degrees.div_euclid(90);
+        Ok(CLOCKWISE_HEADINGS[index.rem_euclid(4) as usize])
+    }
+}
+
+impl Add<Vector> for Point {
+    type Output = Point;
+
+    fn add(self, vector: Vector) -> Point {
+        Point::new(self.x + vector.x, self.y + vector.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Vector;
+
+    fn sub(self, other: Point) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Mul<isize> for Vector {
+    type Output = Vector;
+
+    fn mul(self, scale: isize) -> Vector {
+        Vector::new(self.x * scale, self.y * scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn vector() -> impl Strategy<Value = Vector> {
+        (-1000isize..1000, -1000isize..1000).prop_map(|(x, y)| Vector::new(x, y))
+    }
+
+    #[test]
+    fn rotates_right_angles_exactly() {
+        assert_eq!(Vector::new(10, 4).rotate(-90), Vector::new(4, -10));
+        assert_eq!(Vector::new(10, 4).rotate(180), Vector::new(-10, -4));
+        assert_eq!(Heading::East.rotate(-90).unwrap(), Heading::South);
+        assert_eq!(Heading::North.rotate(450).unwrap(), Heading::West);
+    }
+
+    #[test]
+    fn heading_rejects_partial_turns() {
+        assert_eq!(
+            Heading::North.rotate(45).unwrap_err().to_string(),
+            "Headings only turn by multiples of 90 degrees, got 45"
+        );
+    }
+
+    #[test]
+    fn measures_distance() {
+        let point = Point::new(3, -4);
+
+        assert_eq!(point.manhattan_distance(&Point::ORIGIN), 7);
+        assert!((point.euclidean_distance(&Point::ORIGIN) - 5.0).abs() < f64::EPSILON);
+    }
+
+    proptest! {
+        #[test]
+        fn quarter_turns_compose(vector in vector(), a in -8isize..8, b in -8isize..8) {
+            prop_assert_eq!(
+                vector.rotate(a * 90).rotate(b * 90),
+                vector.rotate((a + b) * 90)
+            );
+            prop_assert_eq!(
+                vector.rotate(a * 90).manhattan_length(),
+                vector.manhattan_length()
+            );
+        }
+
+        #[test]
+        fn heading_turns_like_its_vector(turns in -8isize..8) {
+            [Heading::North, Heading::East, Heading::South, Heading::West]
+                .iter()
+                .try_for_each(|heading| {
+                    prop_assert_eq!(
+                        heading.rotate(turns * 90).unwrap().vector(),
+                        heading.vector().rotate(turns * 90)
+                    );
+                    Ok(())
+                })?;
+        }
+
+        #[test]
+        fn manhattan_distance_obeys_triangle_inequality(a in vector(), b in vector(), c in vector()) {
+            let (a, b, c) = (Point::ORIGIN + a, Point::ORIGIN + b, Point::ORIGIN + c);
+
+            prop_assert!(a.manhattan_distance(&c) <= a.manhattan_distance(&b) + b.manhattan_distance(&c));
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{InputReader, ParseError};
+use anyhow::Error;
+use simple_error::SimpleError;
+use std::fmt;
+use std::iter;
+use std::ops::Index;
+
+/// Column then row, with (0, 0) in the top left.
+pub type Position = (usize, usize);
+
+/// Step in columns then rows, positive is right and down.
+pub type Offset = (isize, isize);
+
+const FOUR_NEIGHBOURS: &[Offset] = &[(0, -1), (-1, 0), (1, 0), (0, 1)];
+const EIGHT_NEIGHBOURS: &[Offset] = &[
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighbourhood {
+    Four,
+    Eight,
+}
+
+impl Neighbourhood {
+    pub fn offsets(&self) -> &'static [Offset] {
+        match self {
+            Neighbourhood::Four => FOUR_NEIGHBOURS,
+            Neighbourhood::Eight => EIGHT_NEIGHBOURS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wrap {
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl Wrap {
+    fn horizontal(&self) -> bool {
+        matches!(self, Wrap::Horizontal | Wrap::Both)
+    }
+
+    fn vertical(&self) -> bool {
+        matches!(self, Wrap::Vertical | Wrap::Both)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, Error> {
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        if width == 0 {
+            return Err(SimpleError::new("A grid needs at least one non-empty row").into());
+        }
+
+        if let Some((index, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != width) {
+            return Err(SimpleError::new(format!(
+                "Row {} is {} wide but the first row is {} wide",
+                index + 1,
+                row.len(),
+                width
+            ))
+            .into());
+        }
+
+        Ok(Grid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Parses one row per line, mapping each character to a tile. Every row must be as wide as
+    /// the first one.
+    pub fn parse<F>(input: &mut InputReader, tile: F, expected: &str) -> Result<Grid<T>, Error>
+    where
+        F: Fn(char) -> Option<T>,
+    {
+        let mut width = None;
+        let rows = input.parse_lines(|line| {
+            let row = parse_row(line, &tile, expected)?;
+            match width {
+                Some(width) if row.len() != width => Err(ParseError::new(
+                    line,
+                    line.char_indices()
+                        .nth(width)
+                        .map(|(offset, _)| offset)
+                        .unwrap_or_else(|| line.len()),
+                    &format!("a row {} tiles wide like the first row", width),
+                )
+                .into()),
+                _ => {
+                    width = Some(row.len());
+                    Ok(row)
+                }
+            }
+        })?;
+
+        if rows.is_empty() {
+            return Err(ParseError::new("", 0, "at least one row")
+                .in_file(input.file_name())
+                .into());
+        }
+
+        Grid::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (x, y): Position) -> Option<&T> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, cell)| ((index % width, index / width), cell))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn map<U, F>(&self, mut f: F) -> Grid<U>
+    where
+        F: FnMut(Position, &T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self
+                .iter()
+                .map(|(position, cell)| f(position, cell))
+                .collect(),
+        }
+    }
+
+    /// Moves from a position by an offset. Returns None when the move leaves the grid along an
+    /// axis that does not wrap.
+    pub fn step(&self, (x, y): Position, (dx, dy): Offset, wrap: Wrap) -> Option<Position> {
+        Some((
+            step_axis(x, dx, self.width, wrap.horizontal())?,
+            step_axis(y, dy, self.height, wrap.vertical())?,
+        ))
+    }
+
+    pub fn neighbours(
+        &self,
+        position: Position,
+        neighbourhood: Neighbourhood,
+        wrap: Wrap,
+    ) -> impl Iterator<Item = (Position, &T)> {
+        neighbourhood
+            .offsets()
+            .iter()
+            .filter_map(move |offset| self.step(position, *offset, wrap))
+            .map(move |neighbour| (neighbour, &self[neighbour]))
+    }
+
+    /// Every cell hit by repeatedly stepping from a position, excluding the position itself.
+    /// Stops when the ray leaves the grid or wraps back around to where it started.
+    pub fn ray(
+        &self,
+        start: Position,
+        direction: Offset,
+        wrap: Wrap,
+    ) -> impl Iterator<Item = (Position, &T)> {
+        iter::successors(self.step(start, direction, wrap), move |position| {
+            self.step(*position, direction, wrap)
+        })
+        .take_while(move |position| *position != start)
+        .map(move |position| (position, &self[position]))
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): Position) -> &T {
+        self.get((x, y)).unwrap_or_else(|| {
+            panic!(
+                "position ({}, {}) is outside of a {}x{} grid",
+                x, y, self.width, self.height
+            )
+        })
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.rows().take(self.height).try_for_each(|row| {
+            row.iter().try_for_each(|cell| write!(f, "{}", cell))?;
+            writeln!(f)
+        })
+    }
+}
+
+fn step_axis(value: usize, delta: isize, size: usize, wrap: bool) -> Option<usize> {
+    let moved = value as isize + delta;
+    if size == 0 {
+        None
+    } else if wrap {
+        Some(moved.rem_euclid(size as isize) as usize)
+    } else if moved >= 0 && (moved as usize) < size {
+        Some(moved as usize)
+    } else {
+        None
+    }
+}
+
+fn parse_row<T, F>(line: &str, tile: &F, expected: &str) -> Result<Vec<T>, Error>
+where
+    F: Fn(char) -> Option<T>,
+{
+    if line.is_empty() {
+        return Err(ParseError::new(line, 0, expected).into());
+    }
+
+    line.char_indices()
+        .map(|(offset, character)| {
+            tile(character).ok_or_else(|| ParseError::new(line, offset, expected).into())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn digit_grid(text: &str) -> Grid<u32> {
+        Grid::parse(
+            &mut InputReader::from_text(text),
+            |character| character.to_digit(10),
+            "digits",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        let err = Grid::from_rows(vec![vec![1, 2], vec![3]]).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Row 2 is 1 wide but the first row is 2 wide"
+        );
+        assert!(Grid::<u32>::from_rows(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn finds_neighbours_inside_the_grid() {
+        let grid = digit_grid("123\n456\n789\n");
+        let neighbours = |position, neighbourhood, wrap| {
+            grid.neighbours(position, neighbourhood, wrap)
+                .map(|(_, value)| *value)
+                .collect::<Vec<u32>>()
+        };
+
+        assert_eq!(
+            neighbours((0, 0), Neighbourhood::Eight, Wrap::None),
+            vec![2, 4, 5]
+        );
+        assert_eq!(
+            neighbours((1, 1), Neighbourhood::Four, Wrap::None),
+            vec![2, 4, 6, 8]
+        );
+        assert_eq!(
+            neighbours((0, 0), Neighbourhood::Four, Wrap::Both),
+            vec![7, 3, 2, 4]
+        );
+    }
+
+    #[test]
+    fn ray_stops_at_the_edge_or_back_at_the_start() {
+        let grid = digit_grid("123\n456\n");
+        let ray = |start, direction, wrap| {
+            grid.ray(start, direction, wrap)
+                .map(|(_, value)| *value)
+                .collect::<Vec<u32>>()
+        };
+
+        assert_eq!(ray((0, 0), (1, 0), Wrap::None), vec![2, 3]);
+        assert_eq!(ray((0, 0), (1, 0), Wrap::Horizontal), vec![2, 3]);
+        assert_eq!(ray((1, 0), (1, 1), Wrap::Horizontal), vec![6]);
+        assert_eq!(ray((0, 0), (0, 0), Wrap::Both), Vec::<u32>::new());
+    }
+
+    proptest! {
+        #[test]
+        fn display_round_trips(rows in prop::collection::vec("[0-9]{1,8}", 1..8)) {
+            let width = rows[0].len();
+            let rows = rows
+                .into_iter()
+                .map(|row| row.chars().cycle().take(width).collect::<String>())
+                .collect::<Vec<String>>();
+            let text = rows.iter().map(|row| format!("{}\n", row)).collect::<String>();
+
+            prop_assert_eq!(digit_grid(&text).to_string(), text);
+        }
+    }
+}
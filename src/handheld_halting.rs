@@ -1,122 +1,166 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_isize, parse_lines, Command};
+use crate::{
+    default_sub_command, flag_or_preset, parse_isize, preset_arg, value_or_preset, GenerateOptions,
+    InputReader, ParseError, Puzzle,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches};
 use nom::{
     bytes::complete::tag,
     character::complete,
-    combinator::{map, map_res},
+    combinator::{all_consuming, map, map_res},
     sequence::separated_pair,
 };
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
 use simple_error::SimpleError;
 use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::fmt;
+use std::io::Write;
 use std::str::FromStr;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const HANDHELD_HALTING: Command = Command::new(sub_command, "handheld-halting", run);
+pub struct HandheldHalting;
 
-#[derive(Debug)]
-struct HandHeldHaltingArgs {
-    file: String,
-    modify: bool,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandHeldHaltingArgs {
+    pub file: String,
+    pub modify: bool,
 }
 
 #[derive(Debug, EnumString, EnumVariantNames, Clone)]
 #[strum(serialize_all = "kebab_case")]
-enum ProgramLine {
+pub enum ProgramLine {
     Acc(isize),
     Jmp(isize),
     Nop(isize),
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &HANDHELD_HALTING,
-        "Takes a file with a simple program (that infinite loops) and finds information about it.",
-        "Path to the input file. Each line contains one instruction",
-    )
-    .arg(
-        Arg::with_name("modify")
-            .short("m")
-            .help("When passed, attempts to modify the input program to remove infinite loop"),
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about(
-                "Finds the value of the accumulator when a loop is detected, or when \
-            the program terminates with default input.",
-            )
-            .version("1.0.0"),
-    )
-    .subcommand(
-        SubCommand::with_name("part2")
-            .about(
-                "Finds the value of the accumulator when a loop is detected, or when \
-            the program terminates with default input, but attempts to correct the program.",
-            )
-            .version("1.0.0"),
-    )
+impl fmt::Display for ProgramLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramLine::Acc(value) => write!(f, "acc {:+}", value),
+            ProgramLine::Jmp(value) => write!(f, "jmp {:+}", value),
+            ProgramLine::Nop(value) => write!(f, "nop {:+}", value),
+        }
+    }
 }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let handheld_halting_arguments = match arguments.subcommand_name() {
-        Some("part1") => HandHeldHaltingArgs {
-            file: "day8/input.txt".to_string(),
-            modify: false,
-        },
-        Some("part2") => HandHeldHaltingArgs {
-            file: "day8/input.txt".to_string(),
-            modify: true,
-        },
-        _ => HandHeldHaltingArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            modify: arguments.is_present("modify"),
-        },
-    };
+impl Puzzle for HandheldHalting {
+    type Args = HandHeldHaltingArgs;
+    type Input = Vec<ProgramLine>;
+    type Answer = isize;
 
-    process_program(&handheld_halting_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
+    const NAME: &'static str = "handheld-halting";
+    const DAY: usize = 8;
+    const GENERATE_ABOUT: &'static str =
+        "Writes a program size instructions long that loops forever, and where exactly one jmp or \
+        nop can be flipped to make it terminate.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Takes a file with a simple program (that infinite loops) and finds information about it.",
+            "Path to the input file. Each line contains one instruction",
+        )
+        .arg(
+            Arg::with_name("modify")
+                .short("m")
+                .help("When passed, attempts to modify the input program to remove infinite loop"),
+        )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds the value of the accumulator when a loop is detected, or when the \
+            program terminates with default input.\n\n\
+            part2: Finds the value of the accumulator when a loop is detected, or when the \
+            program terminates with default input, but attempts to correct the program.\n",
+        ))
+    }
+
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&HandHeldHaltingArgs>,
+    ) -> Result<HandHeldHaltingArgs, Error> {
+        Ok(HandHeldHaltingArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            modify: flag_or_preset(arguments, "modify", preset.map(|preset| &preset.modify)),
         })
-        .map(|_| ())
+    }
+
+    fn part1_args(&self, file: String) -> HandHeldHaltingArgs {
+        HandHeldHaltingArgs {
+            file,
+            modify: false,
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<HandHeldHaltingArgs> {
+        Some(HandHeldHaltingArgs { file, modify: true })
+    }
+
+    fn file<'a>(&self, arguments: &'a HandHeldHaltingArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: HandHeldHaltingArgs, file: String) -> HandHeldHaltingArgs {
+        HandHeldHaltingArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &HandHeldHaltingArgs,
+    ) -> Result<Vec<ProgramLine>, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(
+        &self,
+        program: Vec<ProgramLine>,
+        arguments: &HandHeldHaltingArgs,
+    ) -> Result<isize, Error> {
+        if arguments.modify {
+            find_acc_of_repaired_program(&program)
+        } else {
+            Ok(match compute_acc(&program) {
+                Ok(value) => value,
+                Err(value) => value,
+            })
+        }
+    }
 }
 
-fn process_program(handheld_halting_arguments: &HandHeldHaltingArgs) -> Result<isize, Error> {
-    file_to_lines(&handheld_halting_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_program_line))
-        .map(|program| {
-            let result = compute_acc(&program);
-
-            if !handheld_halting_arguments.modify {
-                match result {
-                    Ok(value) => value,
-                    Err(value) => value,
-                }
-            } else {
-                match result {
-                    Ok(value) => return value,
-                    Err(_) => (),
-                };
-
-                for (index, instruction) in program.clone().into_iter().enumerate() {
-                    let mut new_program = program.clone();
-                    match instruction {
-                        ProgramLine::Acc(_) => continue,
-                        ProgramLine::Jmp(value) => new_program[index] = ProgramLine::Nop(value),
-                        ProgramLine::Nop(value) => new_program[index] = ProgramLine::Jmp(value),
-                    }
-                    match compute_acc(&new_program) {
-                        Ok(value) => return value,
-                        Err(_) => (),
-                    }
-                }
-
-                0
-            }
-        })
+pub fn find_acc_of_repaired_program(program: &[ProgramLine]) -> Result<isize, Error> {
+    if let Ok(value) = compute_acc(program) {
+        return Ok(value);
+    }
+
+    for (index, instruction) in program.iter().enumerate() {
+        let mut new_program = program.to_vec();
+        match instruction {
+            ProgramLine::Acc(_) => continue,
+            ProgramLine::Jmp(value) => new_program[index] = ProgramLine::Nop(*value),
+            ProgramLine::Nop(value) => new_program[index] = ProgramLine::Jmp(*value),
+        }
+        if let Ok(value) = compute_acc(&new_program) {
+            return Ok(value);
+        }
+    }
+
+    Err(SimpleError::new("No single instruction repair terminates").into())
 }
 
-fn compute_acc(program: &Vec<ProgramLine>) -> Result<isize, isize> {
+pub fn compute_acc(program: &[ProgramLine]) -> Result<isize, isize> {
     let mut acc_value = 0;
     let mut visited = HashSet::new();
     let mut program_counter = 0isize;
@@ -148,8 +192,12 @@ fn compute_acc(program: &Vec<ProgramLine>) -> Result<isize, isize> {
     Ok(acc_value)
 }
 
-fn parse_program_line(line: &String) -> Result<ProgramLine, Error> {
-    map(
+pub fn parse_input(input: &mut InputReader) -> Result<Vec<ProgramLine>, Error> {
+    input.parse_lines(parse_program_line)
+}
+
+fn parse_program_line(line: &str) -> Result<ProgramLine, Error> {
+    all_consuming(map(
         separated_pair(
             map_res(complete::alpha1, ProgramLine::from_str),
             tag(" "),
@@ -160,7 +208,107 @@ fn parse_program_line(line: &String) -> Result<ProgramLine, Error> {
             ProgramLine::Jmp(_) => ProgramLine::Jmp(value),
             ProgramLine::Nop(_) => ProgramLine::Nop(value),
         },
-    )(line.as_str())
-    .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse Error").into())
+    ))(line)
+    .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+        ParseError::from_nom(line, err, "acc, jmp or nop followed by a signed integer").into()
+    })
     .map(|(_, instruction)| instruction)
 }
+
+/// Everything before the broken jump eventually reaches it, and everything after it eventually
+/// runs off the end of the program, so flipping the broken jump is the only repair.
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    options.require_size(2, isize::MAX as usize)?;
+    let size = options.size as isize;
+    let broken = rng.gen_range(1..size);
+
+    (0..size)
+        .map(|line| {
+            let end = if line < broken { broken } else { size };
+            match rng.gen_range(0..10) {
+                _ if line == broken => ProgramLine::Jmp(-rng.gen_range(1..=broken)),
+                0..=5 => ProgramLine::Acc(rng.gen_range(-50..=50)),
+                6 | 7 if line < broken => ProgramLine::Nop(rng.gen_range(-line..=broken - line)),
+                6 | 7 => ProgramLine::Nop(rng.gen_range(-size..=size)),
+                _ => ProgramLine::Jmp(rng.gen_range(1..=end - line)),
+            }
+        })
+        .try_for_each(|instruction| writeln!(output, "{}", instruction))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generated_input, sample_input};
+
+    #[test]
+    fn parses_program_line() {
+        assert!(matches!(
+            parse_program_line("nop +0"),
+            Ok(ProgramLine::Nop(0))
+        ));
+        assert!(matches!(
+            parse_program_line("acc -99"),
+            Ok(ProgramLine::Acc(-99))
+        ));
+        assert!(matches!(
+            parse_program_line("jmp +4"),
+            Ok(ProgramLine::Jmp(4))
+        ));
+        assert!(parse_program_line("hcf +1").is_err());
+        assert!(parse_program_line("jmp 4x").is_err());
+    }
+
+    #[test]
+    fn solves_sample() {
+        let program = parse_input(&mut sample_input(8, "sample")).unwrap();
+
+        assert_eq!(compute_acc(&program), Err(5));
+        assert_eq!(find_acc_of_repaired_program(&program).unwrap(), 8);
+    }
+
+    #[test]
+    fn fails_when_no_repair_terminates() {
+        let program = vec![ProgramLine::Jmp(0), ProgramLine::Jmp(-1)];
+
+        assert_eq!(
+            find_acc_of_repaired_program(&program)
+                .unwrap_err()
+                .to_string(),
+            "No single instruction repair terminates"
+        );
+    }
+
+    #[test]
+    fn generated_program_has_exactly_one_repair() {
+        (0..20).for_each(|seed| {
+            let program = parse_input(&mut InputReader::from_text(&generated_input(
+                generate_input,
+                50,
+                seed,
+            )))
+            .unwrap();
+
+            assert!(compute_acc(&program).is_err());
+            let repairs = program
+                .iter()
+                .enumerate()
+                .filter(|(index, instruction)| {
+                    let mut repaired = program.clone();
+                    repaired[*index] = match instruction {
+                        ProgramLine::Acc(_) => return false,
+                        ProgramLine::Jmp(value) => ProgramLine::Nop(*value),
+                        ProgramLine::Nop(value) => ProgramLine::Jmp(*value),
+                    };
+                    compute_acc(&repaired).is_ok()
+                })
+                .count();
+            assert_eq!(repairs, 1, "seed {}", seed);
+        });
+    }
+}
@@ -1,120 +1,164 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_lines, parse_usize, Command};
+use crate::{
+    default_sub_command, parse_usize, preset_arg, random_letter, value_or_preset, GenerateOptions,
+    InputReader, ParseError, Puzzle, PRESET,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches};
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete,
-    combinator::{map, recognize},
+    combinator::{all_consuming, map, recognize},
     multi::fold_many1,
     sequence::{separated_pair, terminated, tuple},
     IResult,
 };
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
 use simple_error::SimpleError;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const HANDY_HAVERSACKS: Command = Command::new(sub_command, "handy-haversacks", run);
+pub struct HandyHaversacks;
 
-#[derive(Debug)]
-struct HandyHaversackArgs {
-    file: String,
-    sack_name: String,
-    count_strategy: SackCountStrategy,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandyHaversackArgs {
+    pub file: String,
+    pub sack_name: String,
+    pub count_strategy: SackCountStrategy,
 }
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+#[derive(Debug, Clone, EnumString, EnumVariantNames, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab_case")]
-enum SackCountStrategy {
+#[serde(rename_all = "kebab-case")]
+pub enum SackCountStrategy {
     CountBagsThatContainTarget,
     CountBagsInTarget,
 }
 
-#[derive(Debug)]
-struct SackRule {
-    sack_name: String,
-    contains: HashMap<String, usize>,
+#[derive(Debug, Clone)]
+pub struct SackRule {
+    pub sack_name: String,
+    pub contains: HashMap<String, usize>,
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &HANDY_HAVERSACKS,
-        "Takes a file with rules about bags in bags in bags and finds interesting facts",
-        "Path to the input file. Each line contains the rules for a bag.",
-    )
-    .arg(
-        Arg::with_name("sack")
-            .short("s")
-            .help("name of the sack you are trying to find stats on.")
-            .takes_value(true)
-            .required(true),
-    )
-    .arg(
-        Arg::with_name("count_strategy")
-            .short("c")
-            .help("What to count for the sack. The strategies are as follows:\n\n\
-            count-bags-that-contain-target: Counts the number of bag that eventually contain the target bag\n\n\
-            count-bags-in-target: Counts the total number of bags inside the target bag.\n")
-            .takes_value(true)
-            .possible_values(&SackCountStrategy::VARIANTS)
-            .required(true),
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about("Finds the number of unique starting bags which contain at least 1 shiny gold bag")
-            .version("1.0.0"),
-    )
-    .subcommand(
-        SubCommand::with_name("part2")
-            .about("Finds the number of bags inside a shiny gold bag")
-            .version("1.0.0"),
-    )
-}
+impl Puzzle for HandyHaversacks {
+    type Args = HandyHaversackArgs;
+    type Input = Vec<SackRule>;
+    type Answer = usize;
+
+    const NAME: &'static str = "handy-haversacks";
+    const DAY: usize = 7;
+    const GENERATE_ABOUT: &'static str =
+        "Writes rules for size bags nested depth levels deep, with shiny gold half way down. \
+        Depth must be between 2 and 30 and size must be greater than depth.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Takes a file with rules about bags in bags in bags and finds interesting facts",
+            "Path to the input file. Each line contains the rules for a bag.",
+        )
+        .arg(
+            Arg::with_name("sack")
+                .short("s")
+                .help("name of the sack you are trying to find stats on.")
+                .takes_value(true)
+                .required_unless(PRESET),
+        )
+        .arg(
+            Arg::with_name("count_strategy")
+                .short("c")
+                .help("What to count for the sack. The strategies are as follows:\n\n\
+                count-bags-that-contain-target: Counts the number of bag that eventually contain the target bag\n\n\
+                count-bags-in-target: Counts the total number of bags inside the target bag.\n")
+                .takes_value(true)
+                .possible_values(SackCountStrategy::VARIANTS)
+                .required_unless(PRESET),
+        )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds the number of unique starting bags which contain at least 1 shiny \
+            gold bag\n\n\
+            part2: Finds the number of bags inside a shiny gold bag\n",
+        ))
+    }
+
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&HandyHaversackArgs>,
+    ) -> Result<HandyHaversackArgs, Error> {
+        Ok(HandyHaversackArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            sack_name: value_or_preset(arguments, "sack", preset.map(|preset| &preset.sack_name))?,
+            count_strategy: value_or_preset(
+                arguments,
+                "count_strategy",
+                preset.map(|preset| &preset.count_strategy),
+            )?,
+        })
+    }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let handy_haversack_arguments = match arguments.subcommand_name() {
-        Some("part1") => HandyHaversackArgs {
-            file: "day7/input.txt".to_string(),
+    fn part1_args(&self, file: String) -> HandyHaversackArgs {
+        HandyHaversackArgs {
+            file,
             sack_name: "shiny gold".to_string(),
             count_strategy: SackCountStrategy::CountBagsThatContainTarget,
-        },
-        Some("part2") => HandyHaversackArgs {
-            file: "day7/input.txt".to_string(),
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<HandyHaversackArgs> {
+        Some(HandyHaversackArgs {
+            file,
             sack_name: "shiny gold".to_string(),
             count_strategy: SackCountStrategy::CountBagsInTarget,
-        },
-        _ => HandyHaversackArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            sack_name: value_t_or_exit!(arguments.value_of("sack"), String),
-            count_strategy: value_t_or_exit!(
-                arguments.value_of("count_strategy"),
-                SackCountStrategy
-            ),
-        },
-    };
-
-    process_sacks(&handy_haversack_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
         })
-        .map(|_| ())
-}
+    }
+
+    fn file<'a>(&self, arguments: &'a HandyHaversackArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: HandyHaversackArgs, file: String) -> HandyHaversackArgs {
+        HandyHaversackArgs { file, ..arguments }
+    }
 
-fn process_sacks(handy_haversack_arguments: &HandyHaversackArgs) -> Result<usize, Error> {
-    file_to_lines(&handy_haversack_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_sack_rules))
-        .map(|rules| match handy_haversack_arguments.count_strategy {
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &HandyHaversackArgs,
+    ) -> Result<Vec<SackRule>, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(&self, rules: Vec<SackRule>, arguments: &HandyHaversackArgs) -> Result<usize, Error> {
+        Ok(match arguments.count_strategy {
             SackCountStrategy::CountBagsThatContainTarget => {
-                find_bags_that_contain(&handy_haversack_arguments.sack_name, rules)
+                find_bags_that_contain(&arguments.sack_name, rules)
             }
             SackCountStrategy::CountBagsInTarget => {
-                find_number_of_bags_in_target(&handy_haversack_arguments.sack_name, rules)
+                find_number_of_bags_in_target(&arguments.sack_name, rules)?
             }
         })
+    }
 }
 
-fn find_number_of_bags_in_target(sack_name: &String, rules: Vec<SackRule>) -> usize {
+pub fn find_number_of_bags_in_target(
+    sack_name: &str,
+    rules: Vec<SackRule>,
+) -> Result<usize, Error> {
     let bag_lookup: HashMap<String, HashMap<String, usize>> = rules
         .into_iter()
         .map(|rule| (rule.sack_name, rule.contains))
@@ -124,29 +168,33 @@ fn find_number_of_bags_in_target(sack_name: &String, rules: Vec<SackRule>) -> us
 }
 
 fn find_bags_in_target(
-    sack_name: &String,
+    sack_name: &str,
     bag_lookup: &HashMap<String, HashMap<String, usize>>,
     cache: &mut HashMap<String, usize>,
-) -> usize {
+) -> Result<usize, Error> {
     match cache.get(sack_name) {
-        Some(value) => *value,
+        Some(value) => Ok(*value),
         None => {
-            let contained_bags = bag_lookup.get(sack_name).unwrap();
+            let contained_bags = bag_lookup
+                .get(sack_name)
+                .ok_or_else(|| SimpleError::new(format!("No rule for bag {}", sack_name)))?;
             let result = contained_bags
                 .iter()
-                .map(|(name, value)| find_bags_in_target(&name, bag_lookup, cache) * value + value)
-                .fold(0, |acc, value| acc + value);
-            cache.insert(sack_name.clone(), result);
-            result
+                .map(|(name, value)| {
+                    find_bags_in_target(name, bag_lookup, cache).map(|bags| bags * value + value)
+                })
+                .sum::<Result<usize, Error>>()?;
+            cache.insert(sack_name.to_string(), result);
+            Ok(result)
         }
     }
 }
 
-fn find_bags_that_contain(sack_name: &String, rules: Vec<SackRule>) -> usize {
+pub fn find_bags_that_contain(sack_name: &str, rules: Vec<SackRule>) -> usize {
     // When you just clone all the things to make the compiler happy, sad times are to be had
     let mut reverse_lookup: HashMap<String, HashSet<String>> = HashMap::new();
     rules.into_iter().for_each(|sack_rule| {
-        sack_rule.contains.keys().into_iter().for_each(|child| {
+        sack_rule.contains.keys().for_each(|child| {
             let mut lookup = if let Some(lookup) = reverse_lookup.get(child) {
                 lookup.clone()
             } else {
@@ -166,7 +214,7 @@ fn find_bags_that_contain(sack_name: &String, rules: Vec<SackRule>) -> usize {
             continue;
         }
         let new_parents = reverse_lookup.get(to_lookup).unwrap();
-        new_parents.into_iter().for_each(|parent| {
+        new_parents.iter().for_each(|parent| {
             if !parents.contains(parent) {
                 parents.insert(parent);
                 queue.push_back(parent);
@@ -177,8 +225,12 @@ fn find_bags_that_contain(sack_name: &String, rules: Vec<SackRule>) -> usize {
     parents.len()
 }
 
-fn parse_sack_rules(line: &String) -> Result<SackRule, Error> {
-    map(
+pub fn parse_input(input: &mut InputReader) -> Result<Vec<SackRule>, Error> {
+    input.parse_lines(parse_sack_rules)
+}
+
+fn parse_sack_rules(line: &str) -> Result<SackRule, Error> {
+    all_consuming(map(
         tuple((
             terminated(parse_sack_name, tag(" contain ")),
             alt((
@@ -199,10 +251,17 @@ fn parse_sack_rules(line: &String) -> Result<SackRule, Error> {
         )),
         |(sack_name, contains)| SackRule {
             sack_name: sack_name.to_string(),
-            contains: contains,
+            contains,
         },
-    )(line)
-    .map_err(|_| SimpleError::new("Parse Error").into())
+    ))(line)
+    .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+        ParseError::from_nom(
+            line,
+            err,
+            "a rule like \"light red bags contain 1 bright white bag.\"",
+        )
+        .into()
+    })
     .map(|(_, sack_rule)| sack_rule)
 }
 
@@ -212,3 +271,159 @@ fn parse_sack_name(input: &str) -> IResult<&str, &str> {
         tuple((tag(" "), alt((tag("bags"), tag("bag"))))),
     )(input)
 }
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    let depth = options.depth;
+    if !(2..=30).contains(&depth) {
+        return Err(
+            SimpleError::new(format!("Depth must be between 2 and 30, got {}", depth)).into(),
+        );
+    }
+    options.require_size(depth + 1, usize::MAX)?;
+
+    let mut levels = vec![Vec::new(); depth + 1];
+    let mut names = HashSet::new();
+    (0..options.size).for_each(|bag| {
+        let level = if bag <= depth {
+            bag
+        } else {
+            rng.gen_range(0..=depth)
+        };
+        let name = if bag == depth / 2 {
+            "shiny gold".to_string()
+        } else {
+            random_sack_name(rng, &names)
+        };
+        names.insert(name.clone());
+        levels[level].push(name);
+    });
+
+    let mut rules = levels
+        .iter()
+        .enumerate()
+        .flat_map(|(level, names)| {
+            names
+                .iter()
+                .enumerate()
+                .map(move |(index, name)| (level, index, name))
+        })
+        .map(|(level, index, name)| {
+            let mut contains: Vec<&String> = Vec::new();
+            if level < depth {
+                // The first bag on each level holds the first bag on the next, so there is always
+                // a chain from the top through shiny gold to the bottom
+                let inner = &levels[level + 1];
+                contains.push(
+                    &inner[if index == 0 {
+                        0
+                    } else {
+                        rng.gen_range(0..inner.len())
+                    }],
+                );
+                (0..rng.gen_range(0..=2)).for_each(|_| {
+                    let inner = &levels[rng.gen_range(level + 1..=depth)];
+                    let child = &inner[rng.gen_range(0..inner.len())];
+                    if !contains.contains(&child) {
+                        contains.push(child);
+                    }
+                });
+            }
+
+            let contains = if contains.is_empty() {
+                "no other bags".to_string()
+            } else {
+                contains
+                    .into_iter()
+                    .map(|child| match rng.gen_range(1..=3) {
+                        1 => format!("1 {} bag", child),
+                        count => format!("{} {} bags", count, child),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+            format!("{} bags contain {}.", name, contains)
+        })
+        .collect::<Vec<String>>();
+    rules.shuffle(rng);
+    rules
+        .iter()
+        .try_for_each(|rule| writeln!(output, "{}", rule))?;
+    Ok(())
+}
+
+fn random_sack_name(rng: &mut dyn RngCore, taken: &HashSet<String>) -> String {
+    let mut word = || {
+        let length = rng.gen_range(3..=7);
+        (0..length).map(|_| random_letter(rng)).collect::<String>()
+    };
+
+    loop {
+        let name = format!("{} {}", word(), word());
+        if name != "shiny gold" && !taken.contains(&name) {
+            return name;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generated_input, sample_input};
+
+    #[test]
+    fn parses_sack_rules() {
+        let rule =
+            parse_sack_rules("light red bags contain 1 bright white bag, 2 muted yellow bags.")
+                .unwrap();
+
+        assert_eq!(rule.sack_name, "light red");
+        assert_eq!(rule.contains.get("bright white"), Some(&1));
+        assert_eq!(rule.contains.get("muted yellow"), Some(&2));
+        assert!(parse_sack_rules("faded blue bags contain no other bags.")
+            .unwrap()
+            .contains
+            .is_empty());
+        assert!(parse_sack_rules("faded blue bags contain").is_err());
+    }
+
+    #[test]
+    fn solves_samples() {
+        let rules = |name| parse_input(&mut sample_input(7, name)).unwrap();
+
+        assert_eq!(find_bags_that_contain("shiny gold", rules("sample")), 4);
+        assert_eq!(
+            find_number_of_bags_in_target("shiny gold", rules("sample")).unwrap(),
+            32
+        );
+        assert_eq!(
+            find_number_of_bags_in_target("shiny gold", rules("sample2")).unwrap(),
+            126
+        );
+        assert_eq!(
+            find_number_of_bags_in_target("dark redd", rules("sample"))
+                .unwrap_err()
+                .to_string(),
+            "No rule for bag dark redd"
+        );
+    }
+
+    #[test]
+    fn generated_rules_nest_around_shiny_gold() {
+        let rules = || {
+            parse_input(&mut InputReader::from_text(&generated_input(
+                generate_input,
+                100,
+                3,
+            )))
+            .unwrap()
+        };
+
+        assert_eq!(rules().len(), 100);
+        assert!(find_bags_that_contain("shiny gold", rules()) >= 3);
+        assert!(find_number_of_bags_in_target("shiny gold", rules()).unwrap() >= 3);
+    }
+}
@@ -1,49 +1,436 @@
-#![feature(const_fn_fn_ptr_basics)]
+pub mod adapter_array;
+pub mod binary_boarding;
+pub mod config;
+pub mod custom_customs;
+pub mod encoding_error;
+pub mod geometry;
+pub mod grid;
+pub mod handheld_halting;
+pub mod handy_haversacks;
+pub mod passport_processing;
+pub mod password_philosophy;
+pub mod rain_risk;
+mod repl;
+pub mod report_repair;
+pub mod seating_system;
+pub mod shuttle_search;
+pub mod toboggan_trajectory;
 
 use anyhow::Error;
-use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use config::{Config, DEFAULT_INPUT};
+use flate2::bufread::MultiGzDecoder;
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::digit1,
     combinator::{map_res, recognize},
     sequence::pair,
-    IResult,
+    IResult, Offset,
 };
+use rand::{Rng, RngCore};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 use simple_error::SimpleError;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-pub struct Command<'a> {
-    sub_command: fn() -> App<'static, 'static>,
-    name: &'a str,
-    run: fn(&ArgMatches) -> Result<(), Error>,
+pub const STDIN_FILE_NAME: &str = "-";
+pub const PRESET: &str = "preset";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+pub trait Puzzle {
+    type Args: Serialize + DeserializeOwned + Clone;
+    type Input: Clone;
+    type Answer: Into<Answer>;
+
+    const NAME: &'static str;
+    const DAY: usize;
+    const GENERATE_ABOUT: &'static str;
+
+    fn sub_command(&self) -> App<'static, 'static>;
+
+    /// Reads the arguments, taking any left out from the preset when one was picked.
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&Self::Args>,
+    ) -> Result<Self::Args, Error>;
+
+    fn part1_args(&self, file: String) -> Self::Args;
+
+    fn part2_args(&self, _file: String) -> Option<Self::Args> {
+        None
+    }
+
+    fn file<'a>(&self, arguments: &'a Self::Args) -> &'a str;
+
+    fn with_file(&self, arguments: Self::Args, file: String) -> Self::Args;
+
+    fn parse(&self, input: &mut InputReader, arguments: &Self::Args) -> Result<Self::Input, Error>;
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error>;
+
+    fn solve(&self, input: Self::Input, arguments: &Self::Args) -> Result<Self::Answer, Error>;
+
+    /// Whether input parsed with one set of arguments can be solved with another. Only puzzles
+    /// whose parse reads the arguments need to override this.
+    fn reuses_input(&self, _parsed_with: &Self::Args, _arguments: &Self::Args) -> bool {
+        true
+    }
+
+    /// A picture of the input for the repl's show and step commands.
+    fn display_input(&self, _input: &Self::Input) -> Option<String> {
+        None
+    }
+
+    /// Advances the input by one round for the repl's step command.
+    fn step(&self, _input: Self::Input, _arguments: &Self::Args) -> Result<Self::Input, Error> {
+        Err(SimpleError::new(format!("{} does not support step", Self::NAME)).into())
+    }
+
+    fn resolve_arguments(
+        &self,
+        arguments: &ArgMatches,
+        config: &Config,
+    ) -> Result<Self::Args, Error> {
+        let preset = match arguments.value_of(PRESET) {
+            Some("part1") => Some(self.part1_args(preset_file(Self::DAY, arguments, config)?)),
+            Some("part2") => Some(
+                self.part2_args(preset_file(Self::DAY, arguments, config)?)
+                    .ok_or_else(|| {
+                        SimpleError::new(format!("{} does not have a part2", Self::NAME))
+                    })?,
+            ),
+            Some(preset) => {
+                return Err(SimpleError::new(format!("Unknown preset {}", preset)).into())
+            }
+            None => None,
+        };
+        self.parse_arguments(arguments, preset.as_ref())
+    }
 }
 
-impl Command<'_> {
-    pub const fn new<'a>(
-        sub_command: fn() -> App<'static, 'static>,
-        name: &'a str,
-        run: fn(&ArgMatches) -> Result<(), Error>,
-    ) -> Command<'a> {
-        Command {
-            sub_command: sub_command,
-            name: name,
-            run: run,
+pub trait Command: Sync {
+    fn name(&self) -> &'static str;
+
+    fn day(&self) -> usize;
+
+    fn generate_about(&self) -> &'static str;
+
+    fn sub_command(&self) -> App<'static, 'static>;
+
+    fn arguments(&self, arguments: &ArgMatches, config: &Config) -> Result<Value, Error>;
+
+    fn input_file(&self, arguments: &ArgMatches, config: &Config) -> Result<String, Error>;
+
+    fn run(
+        &self,
+        arguments: &ArgMatches,
+        config: &Config,
+        timings: &mut Timings,
+    ) -> Result<Answer, Error>;
+
+    /// Like run, but reads the input from file instead of the one the arguments name.
+    fn run_file(
+        &self,
+        arguments: &ArgMatches,
+        config: &Config,
+        file: &str,
+        timings: &mut Timings,
+    ) -> Result<Answer, Error>;
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error>;
+
+    fn repl(
+        &self,
+        arguments: &ArgMatches,
+        config: &Config,
+        commands: &mut dyn BufRead,
+        output: &mut dyn Write,
+    ) -> Result<(), Error>;
+}
+
+impl<P: Puzzle + Sync> Command for P {
+    fn name(&self) -> &'static str {
+        P::NAME
+    }
+
+    fn day(&self) -> usize {
+        P::DAY
+    }
+
+    fn generate_about(&self) -> &'static str {
+        P::GENERATE_ABOUT
+    }
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        Puzzle::sub_command(self)
+    }
+
+    fn arguments(&self, arguments: &ArgMatches, config: &Config) -> Result<Value, Error> {
+        self.resolve_arguments(arguments, config)
+            .and_then(|arguments| serde_json::to_value(arguments).map_err(|err| err.into()))
+    }
+
+    fn input_file(&self, arguments: &ArgMatches, config: &Config) -> Result<String, Error> {
+        self.resolve_arguments(arguments, config)
+            .map(|arguments| self.file(&arguments).to_string())
+    }
+
+    fn run(
+        &self,
+        arguments: &ArgMatches,
+        config: &Config,
+        timings: &mut Timings,
+    ) -> Result<Answer, Error> {
+        let lenient = arguments.is_present("lenient");
+        let arguments = self.resolve_arguments(arguments, config)?;
+        solve_puzzle(self, &arguments, lenient, timings)
+    }
+
+    fn run_file(
+        &self,
+        arguments: &ArgMatches,
+        config: &Config,
+        file: &str,
+        timings: &mut Timings,
+    ) -> Result<Answer, Error> {
+        let lenient = arguments.is_present("lenient");
+        let arguments =
+            self.with_file(self.resolve_arguments(arguments, config)?, file.to_string());
+        solve_puzzle(self, &arguments, lenient, timings)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        Puzzle::generate(self, options, rng, output)
+    }
+
+    fn repl(
+        &self,
+        arguments: &ArgMatches,
+        config: &Config,
+        commands: &mut dyn BufRead,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        repl::run(
+            self,
+            self.resolve_arguments(arguments, config)?,
+            arguments.is_present("lenient"),
+            commands,
+            output,
+        )
+    }
+}
+
+fn solve_puzzle<P: Puzzle>(
+    puzzle: &P,
+    arguments: &P::Args,
+    lenient: bool,
+    timings: &mut Timings,
+) -> Result<Answer, Error> {
+    let input = timings.parse(|| parse_puzzle_input(puzzle, arguments, lenient))?;
+    timings
+        .solve(|| puzzle.solve(input, arguments))
+        .map(|answer| answer.into())
+}
+
+/// Runs a solve, turning a panic inside it into an error so one bad input doesn't take down
+/// everything else that is running.
+pub fn catch_panic<T, F>(solve: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    panic::catch_unwind(AssertUnwindSafe(solve)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown cause".to_string());
+        Err(SimpleError::new(format!("panicked: {}", message)).into())
+    })
+}
+
+fn parse_puzzle_input<P: Puzzle>(
+    puzzle: &P,
+    arguments: &P::Args,
+    lenient: bool,
+) -> Result<P::Input, Error> {
+    let mut input = InputReader::open(puzzle.file(arguments))?.lenient(lenient);
+    let parsed = puzzle.parse(&mut input, arguments)?;
+    input
+        .skipped()
+        .iter()
+        .for_each(|err| eprintln!("warning: skipped {}", err));
+    Ok(parsed)
+}
+
+#[derive(Default)]
+pub struct Registry {
+    commands: Vec<Box<dyn Command>>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    pub fn register<P: Puzzle + Sync + 'static>(mut self, puzzle: P) -> Registry {
+        self.commands.push(Box::new(puzzle));
+        self
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &dyn Command> {
+        self.commands.iter().map(|command| command.as_ref())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Command> {
+        self.commands().find(|command| command.name() == name)
+    }
+}
+
+/// Shape of a generated input. Each puzzle's GENERATE_ABOUT says how it reads these.
+#[derive(Debug, Clone, Copy)]
+pub struct GenerateOptions {
+    pub size: usize,
+    pub width: usize,
+    pub depth: usize,
+}
+
+impl GenerateOptions {
+    pub fn require_size(&self, minimum: usize, maximum: usize) -> Result<(), Error> {
+        if (minimum..=maximum).contains(&self.size) {
+            Ok(())
+        } else {
+            Err(SimpleError::new(format!(
+                "Size must be between {} and {}, got {}",
+                minimum, maximum, self.size
+            ))
+            .into())
         }
     }
+}
+
+pub fn random_letter(rng: &mut dyn RngCore) -> char {
+    (b'a' + rng.gen_range(0..26u8)) as char
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    parse: Duration,
+    solve: Duration,
+}
+
+impl Timings {
+    pub fn new() -> Timings {
+        Timings::default()
+    }
+
+    pub fn parse<T, F: FnOnce() -> T>(&mut self, parse_function: F) -> T {
+        let start = Instant::now();
+        let result = parse_function();
+        self.parse += start.elapsed();
+        result
+    }
+
+    pub fn solve<T, F: FnOnce() -> T>(&mut self, solve_function: F) -> T {
+        let start = Instant::now();
+        let result = solve_function();
+        self.solve += start.elapsed();
+        result
+    }
+
+    pub fn parse_duration(&self) -> Duration {
+        self.parse
+    }
+
+    pub fn solve_duration(&self) -> Duration {
+        self.solve
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Answer {
+    Integer(i128),
+    Text(String),
+    List(Vec<Answer>),
+}
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::Integer(value as i128)
+    }
+}
+
+impl From<isize> for Answer {
+    fn from(value: isize) -> Self {
+        Answer::Integer(value as i128)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::Text(value)
+    }
+}
 
-    pub fn sub_command(&self) -> App<'static, 'static> {
-        (self.sub_command)()
+impl<T: Into<Answer>> From<Vec<T>> for Answer {
+    fn from(values: Vec<T>) -> Self {
+        Answer::List(values.into_iter().map(|value| value.into()).collect())
     }
+}
 
-    pub fn name(&self) -> &str {
-        self.name
+impl Answer {
+    /// How this answer differs from an earlier one, or None if they are the same.
+    pub fn change_from(&self, previous: &Answer) -> Option<String> {
+        match (previous, self) {
+            (previous, answer) if previous == answer => None,
+            (Answer::Integer(previous), Answer::Integer(answer)) => {
+                Some(format!("was {}, {:+}", previous, answer - previous))
+            }
+            (previous, _) => Some(format!("was {}", previous)),
+        }
     }
+}
 
-    pub fn run(&self, arguments: &ArgMatches) -> Result<(), Error> {
-        (self.run)(arguments)
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Integer(value) => write!(f, "{}", value),
+            Answer::Text(value) => write!(f, "{}", value),
+            Answer::List(values) => write!(
+                f,
+                "[{}]",
+                values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
     }
 }
 
@@ -52,6 +439,12 @@ pub struct SumChecker {
     unique_numbers: HashSet<isize>,
 }
 
+impl Default for SumChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SumChecker {
     pub fn new() -> SumChecker {
         SumChecker {
@@ -60,10 +453,10 @@ impl SumChecker {
         }
     }
 
-    pub fn with_vec(input: &Vec<isize>) -> SumChecker {
+    pub fn with_vec(input: &[isize]) -> SumChecker {
         let mut checker = SumChecker::new();
 
-        input.into_iter().for_each(|number| {
+        input.iter().for_each(|number| {
             checker.add_number(*number);
         });
         checker
@@ -81,7 +474,7 @@ impl SumChecker {
     }
 
     pub fn remove_number(&mut self, number: &isize) {
-        let count = self.base_numbers.get(number).unwrap_or(&0usize).clone();
+        let count = *self.base_numbers.get(number).unwrap_or(&0usize);
 
         match count {
             0 => (),
@@ -96,19 +489,21 @@ impl SumChecker {
     }
 
     pub fn find_sum_of_n(&self, target: &isize, n: usize) -> Result<Vec<isize>, Error> {
-        if n == 2 {
+        if n < 2 {
+            Err(SimpleError::new(format!("Sums need at least two numbers, got {}", n)).into())
+        } else if n == 2 {
             self.find_sum(target)
         } else {
-            (&self.unique_numbers)
-                .into_iter()
+            self.unique_numbers
+                .iter()
                 .find_map(|value| {
                     let new_target = target - value;
                     self.find_sum_of_n(&new_target, n - 1)
                         .ok()
                         .filter(|found_values| {
-                            self.base_numbers.get(&value).unwrap_or(&0)
+                            self.base_numbers.get(value).unwrap_or(&0)
                                 > &found_values
-                                    .into_iter()
+                                    .iter()
                                     .filter(|found_value| **found_value == *value)
                                     .count()
                         })
@@ -122,8 +517,8 @@ impl SumChecker {
     }
 
     fn find_sum(&self, target: &isize) -> Result<Vec<isize>, Error> {
-        (&self.unique_numbers)
-            .into_iter()
+        self.unique_numbers
+            .iter()
             .find_map(|value| {
                 self.base_numbers
                     .get_key_value(&(target - value))
@@ -134,77 +529,529 @@ impl SumChecker {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub file: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub expected: String,
+}
+
+impl ParseError {
+    pub fn new(input: &str, offset: usize, expected: &str) -> ParseError {
+        let consumed = &input[..offset];
+        let line_start = consumed.rfind('\n').map(|index| index + 1).unwrap_or(0);
+
+        ParseError {
+            file: None,
+            line: consumed.matches('\n').count() + 1,
+            column: consumed[line_start..].chars().count() + 1,
+            text: input[line_start..].lines().next().unwrap_or("").to_string(),
+            expected: expected.to_string(),
+        }
+    }
+
+    pub fn from_nom(
+        input: &str,
+        err: nom::Err<nom::error::Error<&str>>,
+        expected: &str,
+    ) -> ParseError {
+        let offset = match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => input.offset(err.input),
+            nom::Err::Incomplete(_) => input.len(),
+        };
+        ParseError::new(input, offset, expected)
+    }
+
+    fn on_line(err: Error, line_number: usize, line: &str) -> Vec<ParseError> {
+        match ParseErrors::from_error(err) {
+            Ok(parse_errors) => parse_errors
+                .into_iter()
+                .map(|parse_error| ParseError {
+                    line: line_number + parse_error.line - 1,
+                    ..parse_error
+                })
+                .collect(),
+            Err(err) => vec![ParseError {
+                line: line_number,
+                ..ParseError::new(line, 0, &err.to_string())
+            }],
+        }
+    }
+
+    pub fn in_file(self, file_name: &str) -> ParseError {
+        ParseError {
+            file: Some(file_name.to_string()),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: expected {}, found {:?}",
+            match self.file.as_deref() {
+                Some(STDIN_FILE_NAME) => "<stdin>",
+                Some(file_name) => file_name,
+                None => "<input>",
+            },
+            self.line,
+            self.column,
+            self.expected,
+            self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseErrors(pub Vec<ParseError>);
+
+impl ParseErrors {
+    fn into_error(mut errors: Vec<ParseError>) -> Error {
+        if errors.len() == 1 {
+            errors.remove(0).into()
+        } else {
+            ParseErrors(errors).into()
+        }
+    }
+
+    fn from_error(err: Error) -> Result<Vec<ParseError>, Error> {
+        err.downcast::<ParseErrors>()
+            .map(|ParseErrors(errors)| errors)
+            .or_else(|err| err.downcast::<ParseError>().map(|error| vec![error]))
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} invalid lines:\n{}",
+            self.0.len(),
+            self.0
+                .iter()
+                .map(|err| err.to_string())
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+}
+
+impl std::error::Error for ParseErrors {}
+
 pub fn default_sub_command(
-    command: &Command,
+    name: &'static str,
     about: &'static str,
     file_help: &'static str,
 ) -> App<'static, 'static> {
-    SubCommand::with_name(command.name())
+    SubCommand::with_name(name)
         .about(about)
+        .after_help(
+            "Pass -f - to read the input file from stdin. Gzip and zstd compressed inputs \
+            are decompressed automatically. Pass -f more than once, e.g. -f a.txt -f b.txt, \
+            or a glob such as -f 'inputs/*.txt', to solve each file and print a row per file.\n\n\
+            A preset fills in every argument left out, so part2 -f other.txt solves \
+            other.txt the way part2 solves the default input.",
+        )
         .version("1.0.0")
-        .setting(AppSettings::SubcommandsNegateReqs)
-        .setting(AppSettings::ArgsNegateSubcommands)
         .arg(
             Arg::with_name("file")
                 .short("f")
                 .help(file_help)
                 .takes_value(true)
-                .required(true),
+                .multiple(true)
+                .number_of_values(1)
+                .required_unless(PRESET),
         )
+        .arg(
+            Arg::with_name("input")
+                .long("input")
+                .help(
+                    "Name of the input file in the day directory to use with a preset, e.g. \
+                    sample reads dayN/sample.txt. Defaults to input.",
+                )
+                .takes_value(true)
+                .requires(PRESET)
+                .conflicts_with("file"),
+        )
+}
+
+/// The positional argument picking a preset, list the presets a puzzle has in its help.
+pub fn preset_arg(presets: &'static [&'static str]) -> Arg<'static, 'static> {
+    Arg::with_name(PRESET).index(1).possible_values(presets)
+}
+
+fn preset_file(day: usize, arguments: &ArgMatches, config: &Config) -> Result<String, Error> {
+    match arguments.value_of("file") {
+        Some(file) => Ok(file.to_string()),
+        None => config.input_file(day, arguments.value_of("input").unwrap_or(DEFAULT_INPUT)),
+    }
+}
+
+/// The value passed for an argument, or the preset's when it was left out.
+pub fn value_or_preset<T>(
+    arguments: &ArgMatches,
+    name: &str,
+    preset: Option<&T>,
+) -> Result<T, Error>
+where
+    T: FromStr + Clone,
+    T::Err: fmt::Display,
+{
+    match (arguments.value_of(name), preset) {
+        (Some(value), _) => parse_argument(name, value),
+        (None, Some(preset)) => Ok(preset.clone()),
+        (None, None) => Err(SimpleError::new(format!("Missing argument {}", name)).into()),
+    }
 }
 
-pub fn file_to_lines(file_name: &String) -> Result<Vec<String>, Error> {
-    File::open(file_name)
-        .map_err(|err| err.into())
-        .and_then(|file| {
-            BufReader::new(file)
+pub fn values_or_preset<T>(
+    arguments: &ArgMatches,
+    name: &str,
+    preset: Option<&[T]>,
+) -> Result<Vec<T>, Error>
+where
+    T: FromStr + Clone,
+    T::Err: fmt::Display,
+{
+    match (arguments.values_of(name), preset) {
+        (Some(values), _) => values.map(|value| parse_argument(name, value)).collect(),
+        (None, Some(preset)) => Ok(preset.to_vec()),
+        (None, None) => Err(SimpleError::new(format!("Missing argument {}", name)).into()),
+    }
+}
+
+/// Unlike clap's value_t, keeps the reason the value was rejected.
+fn parse_argument<T>(name: &str, value: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value.parse::<T>().map_err(|err| {
+        SimpleError::new(format!("Invalid value {} for {}: {}", value, name, err)).into()
+    })
+}
+
+/// Flags can only be turned on, so a preset's flag stays on whether or not it is passed.
+pub fn flag_or_preset(arguments: &ArgMatches, name: &str, preset: Option<&bool>) -> bool {
+    arguments.is_present(name) || preset.copied().unwrap_or(false)
+}
+
+pub struct InputReader<'a> {
+    file_name: String,
+    reader: Box<dyn BufRead + 'a>,
+    lenient: bool,
+    skipped: Vec<ParseError>,
+}
+
+impl<'a> InputReader<'a> {
+    pub fn open(file_name: &str) -> Result<InputReader<'static>, Error> {
+        open_input(file_name).map(|reader| InputReader::new(file_name, reader))
+    }
+
+    pub fn from_text(text: &'a str) -> InputReader<'a> {
+        InputReader::new("<input>", Box::new(text.as_bytes()))
+    }
+
+    fn new(file_name: &str, reader: Box<dyn BufRead + 'a>) -> InputReader<'a> {
+        InputReader {
+            file_name: file_name.to_string(),
+            reader,
+            lenient: false,
+            skipped: Vec::new(),
+        }
+    }
+
+    pub fn lenient(self, lenient: bool) -> InputReader<'a> {
+        InputReader { lenient, ..self }
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn skipped(&self) -> &[ParseError] {
+        &self.skipped
+    }
+
+    pub fn lines(&mut self) -> Box<dyn Iterator<Item = Result<String, Error>> + '_> {
+        Box::new(
+            (&mut self.reader)
                 .lines()
-                .try_fold(Vec::new(), |mut lines, line_result| {
-                    line_result.map(|line| {
-                        lines.push(line);
-                        lines
-                    })
+                .map(|line| line.map_err(|err| err.into())),
+        )
+    }
+
+    pub fn read_to_string(&mut self) -> Result<String, Error> {
+        let mut text = String::new();
+        self.reader.read_to_string(&mut text)?;
+        if text.contains('\r') {
+            text = text.replace("\r\n", "\n");
+        }
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    pub fn records(&mut self) -> Records<'_> {
+        Records {
+            lines: self.lines(),
+            line_number: 0,
+        }
+    }
+
+    pub fn parse_lines<U, F>(&mut self, parse_function: F) -> Result<Vec<U>, Error>
+    where
+        F: FnMut(&str) -> Result<U, Error>,
+    {
+        self.fold_lines(Vec::new(), parse_function, push)
+    }
+
+    pub fn parse_records<U, F>(&mut self, parse_function: F) -> Result<Vec<U>, Error>
+    where
+        F: FnMut(&str) -> Result<U, Error>,
+    {
+        self.fold_records(Vec::new(), parse_function, push)
+    }
+
+    /// Like parse_lines, but hands each parsed line to fold_function as it is read instead of
+    /// keeping them all, for puzzles that only need one pass over the input.
+    pub fn fold_lines<U, A, F, G>(
+        &mut self,
+        init: A,
+        parse_function: F,
+        fold_function: G,
+    ) -> Result<A, Error>
+    where
+        F: FnMut(&str) -> Result<U, Error>,
+        G: FnMut(A, U) -> A,
+    {
+        let lines = self
+            .lines()
+            .enumerate()
+            .map(|(index, line)| line.map(|line| (index + 1, line)));
+        let folded = fold_each(lines, init, parse_function, fold_function)?;
+        self.finish(folded)
+    }
+
+    /// The records version of fold_lines.
+    pub fn fold_records<U, A, F, G>(
+        &mut self,
+        init: A,
+        parse_function: F,
+        fold_function: G,
+    ) -> Result<A, Error>
+    where
+        F: FnMut(&str) -> Result<U, Error>,
+        G: FnMut(A, U) -> A,
+    {
+        let records = self
+            .records()
+            .map(|record| record.map(|record| (record.line, record.text())));
+        let folded = fold_each(records, init, parse_function, fold_function)?;
+        self.finish(folded)
+    }
+
+    fn finish<A>(&mut self, (folded, errors): (A, Vec<ParseError>)) -> Result<A, Error> {
+        let errors = errors
+            .into_iter()
+            .map(|err| err.in_file(&self.file_name))
+            .collect::<Vec<ParseError>>();
+
+        if errors.is_empty() || self.lenient {
+            self.skipped.extend(errors);
+            Ok(folded)
+        } else {
+            Err(ParseErrors::into_error(errors))
+        }
+    }
+
+    pub fn parse_text<T, F>(&mut self, mut parse_function: F) -> Result<T, Error>
+    where
+        F: FnMut(&str) -> Result<T, Error>,
+    {
+        let text = self.read_to_string()?;
+        let lines = text.lines().collect::<Vec<&str>>();
+        let mut kept_lines = (0..lines.len()).collect::<Vec<usize>>();
+
+        loop {
+            let input = if kept_lines.len() == lines.len() {
+                Cow::Borrowed(&text)
+            } else {
+                Cow::Owned(kept_lines.iter().fold(String::new(), |mut acc, index| {
+                    acc.push_str(lines[*index]);
+                    acc.push('\n');
+                    acc
+                }))
+            };
+            let err = match parse_function(&input) {
+                Ok(parsed) => return Ok(parsed),
+                Err(err) => err,
+            };
+
+            let errors = ParseErrors::from_error(err)?
+                .into_iter()
+                .map(|err| ParseError {
+                    line: kept_lines
+                        .get(err.line.wrapping_sub(1))
+                        .map(|index| index + 1)
+                        .unwrap_or(lines.len() + 1),
+                    ..err.in_file(&self.file_name)
                 })
-                .map_err(|err| err.into())
-        })
+                .collect::<Vec<ParseError>>();
+            if !self.lenient || errors.iter().any(|err| err.line > lines.len()) {
+                return Err(ParseErrors::into_error(errors));
+            }
+
+            kept_lines.retain(|index| !errors.iter().any(|err| err.line == index + 1));
+            self.skipped.extend(errors);
+        }
+    }
 }
 
-pub fn file_to_string(file_name: &String) -> Result<String, Error> {
-    file_to_lines(file_name).map(|lines| {
-        lines.into_iter().fold(String::new(), |mut acc, line| {
-            acc.push_str(&line.to_string());
-            acc.push('\n');
-            acc
-        })
-    })
+pub struct Record {
+    pub line: usize,
+    pub lines: Vec<String>,
 }
 
-pub fn parse_lines<T, U, E, F>(lines: Vec<T>, mut parse_function: F) -> Result<Vec<U>, E>
+impl Record {
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+pub struct Records<'r> {
+    lines: Box<dyn Iterator<Item = Result<String, Error>> + 'r>,
+    line_number: usize,
+}
+
+impl Iterator for Records<'_> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record: Option<Record> = None;
+
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(err)) => return Some(Err(err)),
+                None => return record.map(Ok),
+            };
+            self.line_number += 1;
+
+            let line = line.trim_end();
+            if !line.is_empty() {
+                let line_number = self.line_number;
+                record
+                    .get_or_insert_with(|| Record {
+                        line: line_number,
+                        lines: Vec::new(),
+                    })
+                    .lines
+                    .push(line.to_string());
+            } else if record.is_some() {
+                return record.map(Ok);
+            }
+        }
+    }
+}
+
+fn open_input(file_name: &str) -> Result<Box<dyn BufRead>, Error> {
+    if file_name == STDIN_FILE_NAME {
+        decompress(Box::new(BufReader::new(io::stdin())))
+    } else {
+        File::open(file_name)
+            .map_err(|err| err.into())
+            .and_then(|file| decompress(Box::new(BufReader::new(file))))
+    }
+}
+
+fn decompress(mut reader: Box<dyn BufRead>) -> Result<Box<dyn BufRead>, Error> {
+    let header = reader.fill_buf()?;
+
+    if header.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else if header.starts_with(ZSTD_MAGIC) {
+        Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(
+            reader,
+        )?)))
+    } else {
+        Ok(reader)
+    }
+}
+
+pub fn file_to_lines(file_name: &str) -> Result<Vec<String>, Error> {
+    InputReader::open(file_name).and_then(|mut input| input.lines().collect())
+}
+
+pub fn file_to_string(file_name: &str) -> Result<String, Error> {
+    InputReader::open(file_name).and_then(|mut input| input.read_to_string())
+}
+
+pub fn parse_file<T, F>(file_name: &str, parse_function: F) -> Result<T, Error>
 where
-    F: FnMut(&T) -> Result<U, E>,
+    F: FnMut(&str) -> Result<T, Error>,
 {
-    lines
-        .into_iter()
-        .try_fold(Vec::new(), |mut parsed_lines, line| {
-            parse_function(&line).map(|parsed_line| {
-                parsed_lines.push(parsed_line);
-                parsed_lines
-            })
-        })
+    InputReader::open(file_name).and_then(|mut input| input.parse_text(parse_function))
 }
 
-pub fn parse_lines_borrowed<T, U, E, F>(lines: Vec<T>, mut parse_function: F) -> Result<Vec<U>, E>
+pub fn parse_lines<I, U, F>(lines: I, parse_function: F) -> Result<Vec<U>, Error>
 where
-    F: FnMut(T) -> Result<U, E>,
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+    F: FnMut(&str) -> Result<U, Error>,
 {
-    lines
+    let lines = lines
         .into_iter()
-        .try_fold(Vec::new(), |mut parsed_lines, line| {
-            parse_function(line).map(|parsed_line| {
-                parsed_lines.push(parsed_line);
-                parsed_lines
+        .enumerate()
+        .map(|(index, line)| Ok((index + 1, line)));
+    fold_each(lines, Vec::new(), parse_function, push).and_then(|(parsed_lines, errors)| {
+        if errors.is_empty() {
+            Ok(parsed_lines)
+        } else {
+            Err(ParseErrors::into_error(errors))
+        }
+    })
+}
+
+fn fold_each<I, S, U, A, F, G>(
+    mut numbered_inputs: I,
+    init: A,
+    mut parse_function: F,
+    mut fold_function: G,
+) -> Result<(A, Vec<ParseError>), Error>
+where
+    I: Iterator<Item = Result<(usize, S), Error>>,
+    S: AsRef<str>,
+    F: FnMut(&str) -> Result<U, Error>,
+    G: FnMut(A, U) -> A,
+{
+    numbered_inputs.try_fold(
+        (init, Vec::new()),
+        |(folded, mut errors), numbered_input| {
+            numbered_input.map(|(line_number, input)| {
+                let input = input.as_ref();
+                match parse_function(input) {
+                    Ok(value) => (fold_function(folded, value), errors),
+                    Err(err) => {
+                        errors.extend(ParseError::on_line(err, line_number, input));
+                        (folded, errors)
+                    }
+                }
             })
-        })
+        },
+    )
+}
+
+fn push<U>(mut parsed: Vec<U>, value: U) -> Vec<U> {
+    parsed.push(value);
+    parsed
 }
 
 pub fn parse_usize(input: &str) -> IResult<&str, usize> {
@@ -212,12 +1059,306 @@ pub fn parse_usize(input: &str) -> IResult<&str, usize> {
 }
 
 fn usisze_from_string(input: &str) -> Result<usize, Error> {
-    usize::from_str_radix(input, 10).map_err(|err| err.into())
+    input.parse::<usize>().map_err(|err| err.into())
 }
 
 pub fn parse_isize(input: &str) -> IResult<&str, isize> {
     map_res(
         recognize(pair(alt((tag("+"), tag("-"), tag(""))), digit1)),
-        |value| isize::from_str_radix(value, 10),
+        |value: &str| value.parse::<isize>(),
     )(input)
 }
+
+#[cfg(test)]
+pub(crate) fn sample_input(day: usize, name: &str) -> InputReader<'static> {
+    InputReader::open(&format!(
+        "{}/day{}/{}.txt",
+        env!("CARGO_MANIFEST_DIR"),
+        day,
+        name
+    ))
+    .expect("sample input should exist")
+}
+
+#[cfg(test)]
+pub(crate) fn generated_input<F>(generate: F, size: usize, seed: u64) -> String
+where
+    F: Fn(&GenerateOptions, &mut dyn RngCore, &mut dyn Write) -> Result<(), Error>,
+{
+    use rand::SeedableRng;
+
+    let mut output = Vec::new();
+    generate(
+        &GenerateOptions {
+            size,
+            width: size,
+            depth: 6,
+        },
+        &mut rand_chacha::ChaCha8Rng::seed_from_u64(seed),
+        &mut output,
+    )
+    .expect("generator should succeed");
+    String::from_utf8(output).expect("generated input should be utf-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use proptest::prelude::*;
+    use std::io::{Cursor, Read, Write};
+
+    fn parse_integer(line: &str) -> Result<usize, Error> {
+        line.parse::<usize>()
+            .map_err(|_| ParseError::new(line, 0, "an integer").into())
+    }
+
+    #[test]
+    fn parse_error_finds_line_and_column() {
+        let err = ParseError::new("abc\ndef\n", 5, "a digit");
+
+        assert_eq!(err.line, 2);
+        assert_eq!(err.column, 2);
+        assert_eq!(err.text, "def");
+        assert_eq!(
+            err.to_string(),
+            "<input>:2:2: expected a digit, found \"def\""
+        );
+    }
+
+    #[test]
+    fn parse_lines_reports_every_invalid_line() {
+        let err = InputReader::from_text("1\nx\n3\ny\n")
+            .parse_lines(parse_integer)
+            .unwrap_err();
+        let errors = err.downcast::<ParseErrors>().unwrap();
+
+        assert_eq!(
+            errors.0.iter().map(|err| err.line).collect::<Vec<usize>>(),
+            vec![2, 4]
+        );
+    }
+
+    #[test]
+    fn lenient_parse_lines_skips_invalid_lines() {
+        let mut input = InputReader::from_text("1\nx\n3\n").lenient(true);
+
+        assert_eq!(input.parse_lines(parse_integer).unwrap(), vec![1, 3]);
+        assert_eq!(input.skipped().len(), 1);
+        assert_eq!(input.skipped()[0].line, 2);
+    }
+
+    #[test]
+    fn fold_lines_folds_without_keeping_lines() {
+        let mut input = InputReader::from_text("1\nx\n3\n").lenient(true);
+
+        assert_eq!(
+            input
+                .fold_lines(0, parse_integer, |sum, number| sum + number)
+                .unwrap(),
+            4
+        );
+        assert_eq!(input.skipped()[0].line, 2);
+        assert!(InputReader::from_text("1\nx\n")
+            .fold_lines(0, parse_integer, |sum, number| sum + number)
+            .is_err());
+    }
+
+    #[test]
+    fn records_are_separated_by_runs_of_blank_lines() {
+        let records = InputReader::from_text("a b\nc\r\n\n\n\nd\ne")
+            .records()
+            .collect::<Result<Vec<Record>, Error>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            (records[0].line, records[0].text()),
+            (1, "a b\nc".to_string())
+        );
+        assert_eq!(
+            (records[1].line, records[1].text()),
+            (6, "d\ne".to_string())
+        );
+    }
+
+    #[test]
+    fn nested_line_errors_keep_their_own_lines_within_a_record() {
+        let err = InputReader::from_text("1\n2\n\n3\nx\n5\ny\n")
+            .parse_records(|record| parse_lines(record.lines(), parse_integer))
+            .unwrap_err();
+        let errors = err.downcast::<ParseErrors>().unwrap();
+
+        assert_eq!(
+            errors
+                .0
+                .iter()
+                .map(|err| (err.line, err.text.as_str()))
+                .collect::<Vec<(usize, &str)>>(),
+            vec![(5, "x"), (7, "y")]
+        );
+        assert_eq!(errors.0[0].expected, "an integer");
+    }
+
+    #[test]
+    fn parse_text_maps_lenient_errors_back_to_original_lines() {
+        let mut input = InputReader::from_text("1\n2\nx\n4\n").lenient(true);
+        let sum = input
+            .parse_text(|text| {
+                parse_lines(text.lines(), parse_integer)
+                    .map(|numbers| numbers.iter().sum::<usize>())
+            })
+            .unwrap();
+
+        assert_eq!(sum, 7);
+        assert_eq!(input.skipped()[0].line, 3);
+    }
+
+    #[test]
+    fn gzip_input_is_decompressed() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"1\n2\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut text = String::new();
+        decompress(Box::new(Cursor::new(compressed)))
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+
+        assert_eq!(text, "1\n2\n");
+    }
+
+    #[test]
+    fn describes_how_an_answer_changed() {
+        assert_eq!(
+            Answer::Integer(37).change_from(&Answer::Integer(40)),
+            Some("was 40, -3".to_string())
+        );
+        assert_eq!(Answer::Integer(37).change_from(&Answer::Integer(37)), None);
+        assert_eq!(
+            Answer::Text("abc".to_string()).change_from(&Answer::Integer(1)),
+            Some("was 1".to_string())
+        );
+    }
+
+    #[test]
+    fn run_file_solves_the_given_file_with_the_other_arguments() {
+        let puzzle = seating_system::SeatingSystem;
+        let arguments = Command::sub_command(&puzzle).get_matches_from(vec![
+            "seating-system",
+            "-f",
+            "first.txt",
+            "-f",
+            "second.txt",
+            "-t",
+            "5",
+            "-a",
+            "line-of-sight",
+        ]);
+        let file = format!("{}/day11/sample.txt", env!("CARGO_MANIFEST_DIR"));
+
+        assert_eq!(
+            arguments.values_of("file").unwrap().collect::<Vec<&str>>(),
+            vec!["first.txt", "second.txt"]
+        );
+        assert_eq!(
+            puzzle
+                .run_file(&arguments, &Config::default(), &file, &mut Timings::new())
+                .unwrap(),
+            Answer::Integer(26)
+        );
+    }
+
+    #[test]
+    fn file_before_a_preset_is_not_taken_as_a_second_file() {
+        let file = format!("{}/day11/sample.txt", env!("CARGO_MANIFEST_DIR"));
+        let puzzle = seating_system::SeatingSystem;
+        let arguments = puzzle
+            .resolve_arguments(
+                &Command::sub_command(&puzzle).get_matches_from(vec![
+                    "seating-system",
+                    "-f",
+                    &file,
+                    "part2",
+                ]),
+                &Config::default(),
+            )
+            .unwrap();
+
+        assert_eq!(arguments.file, file);
+        assert_eq!(arguments.tolerance, 5);
+    }
+
+    #[test]
+    fn presets_are_overridden_by_arguments_passed_with_them() {
+        let file = format!("{}/day7/sample.txt", env!("CARGO_MANIFEST_DIR"));
+        let puzzle = handy_haversacks::HandyHaversacks;
+        let arguments = puzzle
+            .resolve_arguments(
+                &Command::sub_command(&puzzle).get_matches_from(vec![
+                    "handy-haversacks",
+                    "part1",
+                    "-f",
+                    &file,
+                    "-s",
+                    "dark red",
+                ]),
+                &Config::default(),
+            )
+            .unwrap();
+
+        assert_eq!(arguments.file, file);
+        assert_eq!(arguments.sack_name, "dark red");
+        assert!(matches!(
+            arguments.count_strategy,
+            handy_haversacks::SackCountStrategy::CountBagsThatContainTarget
+        ));
+    }
+
+    #[test]
+    fn catch_panic_reports_the_panic_as_an_error() {
+        assert_eq!(catch_panic(|| Ok(3)).unwrap(), 3);
+        assert_eq!(
+            catch_panic::<usize, _>(|| panic!("bad input {}", 7))
+                .unwrap_err()
+                .to_string(),
+            "panicked: bad input 7"
+        );
+    }
+
+    #[test]
+    fn find_sum_of_n_needs_at_least_two_numbers() {
+        let checker = SumChecker::with_vec(&[1, 2, 3]);
+
+        assert!(checker.find_sum_of_n(&3, 1).is_err());
+        assert!(checker.find_sum_of_n(&0, 0).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn find_sum_of_n_sums_to_target(
+            numbers in prop::collection::vec(-1000isize..1000, 3..30),
+            n in 2usize..=3,
+        ) {
+            let target = numbers[..n].iter().sum::<isize>();
+            let found = SumChecker::with_vec(&numbers).find_sum_of_n(&target, n).unwrap();
+
+            prop_assert_eq!(found.len(), n);
+            prop_assert_eq!(found.iter().sum::<isize>(), target);
+            found.iter().try_for_each(|value| {
+                prop_assert!(
+                    found.iter().filter(|other| *other == value).count()
+                        <= numbers.iter().filter(|other| *other == value).count()
+                );
+                Ok(())
+            })?;
+        }
+
+        #[test]
+        fn parse_isize_reads_any_integer(number in any::<isize>()) {
+            let text = number.to_string();
+            prop_assert_eq!(parse_isize(&text), Ok(("", number)));
+        }
+    }
+}
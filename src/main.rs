@@ -1,70 +1,1197 @@
-#![feature(const_fn_fn_ptr_basics)]
-#![feature(iterator_fold_self)]
-
-mod adapter_array;
-mod binary_boarding;
-mod custom_customs;
-mod encoding_error;
-mod handheld_halting;
-mod handy_haversacks;
-mod lib;
-mod passport_processing;
-mod password_philosophy;
-mod rain_risk;
-mod report_repair;
-mod seating_system;
-mod shuttle_search;
-mod toboggan_trajectory;
-
+use adventofcode2020::{
+    adapter_array, binary_boarding, catch_panic,
+    config::{Config, DEFAULT_INPUT},
+    custom_customs, encoding_error, file_to_lines, handheld_halting, handy_haversacks, parse_lines,
+    passport_processing, password_philosophy, rain_risk, report_repair, seating_system,
+    shuttle_search, toboggan_trajectory, Answer, Command, GenerateOptions, Registry, Timings,
+    PRESET, STDIN_FILE_NAME,
+};
 use anyhow::Error;
-use clap::{App, AppSettings};
-use lib::Command;
+use clap::{value_t_or_exit, App, AppSettings, Arg, ArgMatches, ErrorKind, Shell, SubCommand};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+use serde_json::Value;
 use simple_error::SimpleError;
-use std::collections::HashMap;
-
-const VERSION: &'static str = env!("CARGO_PKG_VERSION");
-const COMMANDS: &'static [Command] = &[
-    toboggan_trajectory::TOBOGGAN_TRAJECTORY,
-    password_philosophy::PASSWORD_PHILOSOPHY,
-    report_repair::REPORT_REPAIR,
-    passport_processing::PASSPORT_PROCESSING,
-    binary_boarding::BINARY_BOARDING,
-    custom_customs::CUSTOM_CUSTOMS,
-    handy_haversacks::HANDY_HAVERSACKS,
-    handheld_halting::HANDHELD_HALTING,
-    encoding_error::ENCODING_ERROR,
-    adapter_array::ADAPTER_ARRAY,
-    seating_system::SEATING_SYSTEM,
-    rain_risk::RAIN_RISK,
-    shuttle_search::SHUTTLE_SEARCH,
-];
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::iter;
+use std::panic;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use strum::VariantNames;
+use strum_macros::{EnumString, EnumVariantNames};
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const BIN_NAME: &str = env!("CARGO_BIN_NAME");
+const PRESETS: &[&str] = &["part1", "part2"];
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+fn registry() -> Registry {
+    Registry::new()
+        .register(toboggan_trajectory::TobogganTrajectory)
+        .register(password_philosophy::PasswordPhilosophy)
+        .register(report_repair::ReportRepair)
+        .register(passport_processing::PassportProcessing)
+        .register(binary_boarding::BinaryBoarding)
+        .register(custom_customs::CustomCustoms)
+        .register(handy_haversacks::HandyHaversacks)
+        .register(handheld_halting::HandheldHalting)
+        .register(encoding_error::EncodingError)
+        .register(adapter_array::AdapterArray)
+        .register(seating_system::SeatingSystem)
+        .register(rain_risk::RainRisk)
+        .register(shuttle_search::ShuttleSearch)
+}
+
+#[derive(Debug, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "kebab_case")]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct RunReport {
+    command: &'static str,
+    preset: Option<String>,
+    input: Option<String>,
+    arguments: Value,
+    answer: Option<Answer>,
+    elapsed_ms: f64,
+    parse_ms: f64,
+    solve_ms: f64,
+    error: Option<String>,
+}
+
+impl RunReport {
+    fn failed(
+        command: &dyn Command,
+        preset: Option<&str>,
+        input: Option<&str>,
+        error: Error,
+    ) -> RunReport {
+        RunReport {
+            command: command.name(),
+            preset: preset.map(|preset| preset.to_string()),
+            input: input.map(|input| input.to_string()),
+            arguments: Value::Null,
+            answer: None,
+            elapsed_ms: 0.0,
+            parse_ms: 0.0,
+            solve_ms: 0.0,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ExpectedAnswer {
+    preset: String,
+    input: String,
+    answer: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Verification {
+    #[serde(flatten)]
+    report: RunReport,
+    expected: String,
+    matches: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct FileReport {
+    command: &'static str,
+    file: String,
+    answer: Option<Answer>,
+    elapsed_ms: f64,
+    parse_ms: f64,
+    solve_ms: f64,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct WatchReport<'a> {
+    #[serde(flatten)]
+    report: &'a RunReport,
+    change: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BenchReport {
+    command: &'static str,
+    preset: Option<String>,
+    arguments: Value,
+    iterations: usize,
+    parse: DurationStats,
+    solve: DurationStats,
+    total: DurationStats,
+}
+
+#[derive(Debug, Serialize)]
+struct DurationStats {
+    min_ms: f64,
+    median_ms: f64,
+    max_ms: f64,
+}
+
+impl DurationStats {
+    fn from_durations(mut durations: Vec<Duration>) -> DurationStats {
+        durations.sort();
+        let middle = durations.len() / 2;
+        let median = if durations.len().is_multiple_of(2) {
+            (durations[middle - 1] + durations[middle]) / 2
+        } else {
+            durations[middle]
+        };
+
+        DurationStats {
+            min_ms: to_ms(durations[0]),
+            median_ms: to_ms(median),
+            max_ms: to_ms(durations[durations.len() - 1]),
+        }
+    }
+}
 
 fn main() -> Result<(), Error> {
+    let registry = registry();
+    let raw_args = std::env::args().collect::<Vec<String>>();
+    let json_command = json_command(&registry, &raw_args);
+    let app = match json_command {
+        Some(_) => app(&registry).setting(AppSettings::ColorNever),
+        None => app(&registry),
+    };
+    let matches = match (app.get_matches_from_safe(&raw_args), json_command) {
+        (Ok(matches), _) => matches,
+        (Err(err), Some(command))
+            if err.kind != ErrorKind::HelpDisplayed && err.kind != ErrorKind::VersionDisplayed =>
+        {
+            let message = err.message.lines().next().unwrap_or("").to_string();
+            let message = message.trim_start_matches("error: ").to_string();
+            return fail_as_json(command, None, SimpleError::new(message).into());
+        }
+        (Err(err), _) => err.exit(),
+    };
+    // Completion scripts don't depend on the config, so a broken config shouldn't stop them
+    if let ("completions", Some(args)) = matches.subcommand() {
+        return completions(&registry, args);
+    }
+    let config = match (Config::load(), json_command) {
+        (Ok(config), _) => config,
+        (Err(err), Some(command)) => {
+            let preset = matches
+                .subcommand()
+                .1
+                .and_then(|args| args.value_of(PRESET));
+            return fail_as_json(command, preset, err);
+        }
+        (Err(err), None) => return Err(err),
+    };
+
+    if let ("run-all", Some(args)) = matches.subcommand() {
+        run_all(
+            &registry,
+            &config,
+            &value_t_or_exit!(args.value_of("format"), OutputFormat),
+        )
+    } else if let ("verify", Some(args)) = matches.subcommand() {
+        verify(
+            &registry,
+            &config,
+            &value_t_or_exit!(args.value_of("format"), OutputFormat),
+        )
+    } else if let ("bench", Some(args)) = matches.subcommand() {
+        bench(&registry, &config, args)
+    } else if let ("generate", Some(args)) = matches.subcommand() {
+        generate(&registry, args)
+    } else if let ("repl", Some(args)) = matches.subcommand() {
+        repl(&registry, &config, args)
+    } else if let (command_name, Some(args)) = matches.subcommand() {
+        registry
+            .get(command_name)
+            .ok_or_else::<Error, _>(|| SimpleError::new("No valid subcommand found").into())
+            .and_then(|command| {
+                let format = value_t_or_exit!(args.value_of("format"), OutputFormat);
+                match input_files(args)? {
+                    Some(files) => run_files(command, &config, args, &files, &format),
+                    None if args.is_present("watch") => watch(command, &config, args, &format),
+                    None => match format {
+                        OutputFormat::Text => run_text(command, &config, args),
+                        OutputFormat::Json => run_json(command, &config, args),
+                    },
+                }
+            })
+    } else {
+        Err(SimpleError::new("No arguments found").into())
+    }
+}
+
+fn app(registry: &Registry) -> App<'static, 'static> {
     let app = App::new("Advent of code 2020")
         .version(VERSION)
         .author("Kevin Simpson <ktsimpso@gmail.com>")
         .about("Run advent of code problems from this main program")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .after_help(
+            "The part1 and part2 presets read dayN/input.txt, or the file picked with --input, from \
+            the directory set by AOC_INPUT_DIR or by input_dir in the config file, unless -f names \
+            another file. Any other argument passed with a preset overrides the preset's. The \
+            config file is read from AOC_CONFIG or $XDG_CONFIG_HOME/adventofcode2020/config.toml \
+            and may also set a directory per day under [days].",
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .help(
+                    "How to print the result of a run. The possible formats are as follows:\n\n\
+                text: A banner with the command name followed by the answer.\n\n\
+                json: One JSON object with the command, arguments, answer, elapsed time and error.\n",
+                )
+                .takes_value(true)
+                .possible_values(OutputFormat::VARIANTS)
+                .default_value("text")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("time")
+                .long("time")
+                .help("When passed, prints the parse and solve time after the answer.")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("watch")
+                .long("watch")
+                .help(
+                    "When passed, keeps running and solves again whenever the input file is \
+                    saved, printing how the answer changed. Stop with Ctrl-C.",
+                )
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("lenient")
+                .long("lenient")
+                .help(
+                    "When passed, input lines that fail to parse are skipped and printed as \
+                    warnings instead of failing the run.",
+                )
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("run-all")
+                .about(
+                    "Runs the part1 and part2 presets of every command and prints a summary table. \
+                    Failures are reported per row.",
+                )
+                .version("1.0.0"),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about(
+                    "Runs every preset listed in a dayN/answers.txt file and compares the result \
+                    with the expected answer. Exits non-zero on any mismatch. Lines are \
+                    \"preset: answer\" for the default input or \"preset input: answer\" for \
+                    another input in the day directory, e.g. \"part1 sample: 7\".",
+                )
+                .version("1.0.0"),
+        )
+        .subcommand(
+            SubCommand::with_name("completions")
+                .about(
+                    "Prints a completion script for a shell covering every command, preset and \
+                    strategy value. For example, add \"source <(adventofcode2020 completions \
+                    bash)\" to ~/.bashrc.",
+                )
+                .version("1.0.0")
+                .arg(
+                    Arg::with_name("shell")
+                        .help("Shell to print the completion script for.")
+                        .possible_values(&Shell::variants())
+                        .required(true),
+                ),
+        );
+    let bench_command = SubCommand::with_name("bench")
+        .about(
+            "Runs a command N times and reports the min, median and max wall time \
+            of its parse and solve phases.",
+        )
+        .version("1.0.0")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("iterations")
+                .short("n")
+                .help("Number of times to run the command.")
+                .takes_value(true)
+                .default_value("10"),
+        );
+
+    let generate_command = SubCommand::with_name("generate")
+        .about(
+            "Writes a random but valid input for a command. The same seed and sizes always \
+            produce the same input.",
+        )
+        .version("1.0.0")
+        .setting(AppSettings::SubcommandRequiredElseHelp);
+
+    let repl_command = SubCommand::with_name("repl")
+        .about(
+            "Parses a command's input once, then reads commands from stdin to change its \
+            arguments and solve it again without parsing. Type help inside for the commands.",
+        )
+        .version("1.0.0")
         .setting(AppSettings::SubcommandRequiredElseHelp);
 
-    let matches = COMMANDS
+    let (app, bench_command, generate_command, repl_command) = registry.commands().fold(
+        (app, bench_command, generate_command, repl_command),
+        |(app, bench_command, generate_command, repl_command), command| {
+            (
+                app.subcommand(command.sub_command()),
+                bench_command.subcommand(command.sub_command()),
+                generate_command.subcommand(generate_sub_command(command)),
+                repl_command.subcommand(command.sub_command()),
+            )
+        },
+    );
+    app.subcommand(bench_command)
+        .subcommand(generate_command)
+        .subcommand(repl_command)
+}
+
+/// The command to report on when --format json is asked for, found before clap parses anything
+/// so that argument and config errors can be reported as JSON too.
+fn json_command<'a>(registry: &'a Registry, raw_args: &[String]) -> Option<&'a dyn Command> {
+    let json = raw_args.iter().any(|arg| arg == "--format=json")
+        || raw_args
+            .windows(2)
+            .any(|pair| pair[0] == "--format" && pair[1] == "json");
+    if json {
+        raw_args.iter().find_map(|arg| registry.get(arg))
+    } else {
+        None
+    }
+}
+
+fn fail_as_json(command: &dyn Command, preset: Option<&str>, error: Error) -> Result<(), Error> {
+    let report = RunReport::failed(command, preset, None, error);
+    println!("{}", serde_json::to_string(&report)?);
+    std::process::exit(1);
+}
+
+fn completions(registry: &Registry, args: &ArgMatches) -> Result<(), Error> {
+    let mut script = Vec::new();
+    app(registry).gen_completions_to(
+        BIN_NAME,
+        value_t_or_exit!(args.value_of("shell"), Shell),
+        &mut script,
+    );
+    io::stdout().write_all(&script)?;
+    Ok(())
+}
+
+fn run_text(command: &dyn Command, config: &Config, args: &ArgMatches) -> Result<(), Error> {
+    println!("=============Running {:}=============", command.name());
+    let mut timings = Timings::new();
+    let start = Instant::now();
+    let answer = command.run(args, config, &mut timings)?;
+    let elapsed = start.elapsed();
+
+    println!("{}", answer);
+    if args.is_present("time") {
+        println!(
+            "parse: {:.3} ms, solve: {:.3} ms, total: {:.3} ms",
+            to_ms(timings.parse_duration()),
+            to_ms(timings.solve_duration()),
+            to_ms(elapsed)
+        );
+    }
+    Ok(())
+}
+
+fn run_json(command: &dyn Command, config: &Config, args: &ArgMatches) -> Result<(), Error> {
+    let report = execute(command, config, args);
+    println!("{}", serde_json::to_string(&report)?);
+
+    if report.error.is_some() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The files to solve one by one when -f names more than one, or a glob. None for a single file.
+fn input_files(args: &ArgMatches) -> Result<Option<Vec<String>>, Error> {
+    let patterns = match args.values_of("file") {
+        Some(patterns) => patterns.collect::<Vec<&str>>(),
+        None => return Ok(None),
+    };
+    let is_glob = |pattern: &str| glob::Pattern::escape(pattern) != pattern;
+    if patterns.len() == 1 && !is_glob(patterns[0]) {
+        return Ok(None);
+    }
+
+    let files = patterns
+        .iter()
+        .map(|pattern| {
+            if !is_glob(pattern) {
+                return Ok(vec![pattern.to_string()]);
+            }
+            let files = glob::glob(pattern)?
+                .map(|path| path.map(|path| path.to_string_lossy().into_owned()))
+                .collect::<Result<Vec<String>, _>>()?;
+            if files.is_empty() {
+                Err(SimpleError::new(format!("No files match {}", pattern)).into())
+            } else {
+                Ok(files)
+            }
+        })
+        .collect::<Result<Vec<Vec<String>>, Error>>()?
+        .concat();
+
+    if files.iter().any(|file| file == STDIN_FILE_NAME) {
+        return Err(SimpleError::new("Cannot read stdin alongside other files").into());
+    }
+    Ok(Some(files))
+}
+
+fn run_files(
+    command: &dyn Command,
+    config: &Config,
+    args: &ArgMatches,
+    files: &[String],
+    format: &OutputFormat,
+) -> Result<(), Error> {
+    if args.is_present("watch") {
+        return Err(SimpleError::new("Cannot watch more than one file").into());
+    }
+
+    let reports = execute_files(command, config, args, files);
+    match format {
+        OutputFormat::Text => print_file_table(&reports),
+        OutputFormat::Json => reports.iter().try_for_each(|report| {
+            serde_json::to_string(report).map(|line| {
+                println!("{}", line);
+            })
+        })?,
+    };
+
+    if reports.iter().any(|report| report.error.is_some()) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Solves every file on a pool of worker threads, returning the reports in the order of files.
+fn execute_files(
+    command: &dyn Command,
+    config: &Config,
+    args: &ArgMatches,
+    files: &[String],
+) -> Vec<FileReport> {
+    let workers = thread::available_parallelism()
+        .map(|workers| workers.get())
+        .unwrap_or(1)
+        .min(files.len());
+    let next = AtomicUsize::new(0);
+
+    let mut reports = thread::scope(|scope| {
+        (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    iter::from_fn(|| {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        files
+                            .get(index)
+                            .map(|file| (index, execute_file(command, config, args, file)))
+                    })
+                    .collect::<Vec<(usize, FileReport)>>()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| panic::resume_unwind(panic))
+            })
+            .collect::<Vec<(usize, FileReport)>>()
+    });
+
+    reports.sort_by_key(|(index, _)| *index);
+    reports.into_iter().map(|(_, report)| report).collect()
+}
+
+fn execute_file(
+    command: &dyn Command,
+    config: &Config,
+    args: &ArgMatches,
+    file: &str,
+) -> FileReport {
+    let mut timings = Timings::new();
+    let start = Instant::now();
+    let result = catch_panic(|| command.run_file(args, config, file, &mut timings));
+    let elapsed = start.elapsed();
+
+    let (answer, error) = match result {
+        Ok(answer) => (Some(answer), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+
+    FileReport {
+        command: command.name(),
+        file: file.to_string(),
+        answer,
+        elapsed_ms: to_ms(elapsed),
+        parse_ms: to_ms(timings.parse_duration()),
+        solve_ms: to_ms(timings.solve_duration()),
+        error,
+    }
+}
+
+fn watch(
+    command: &dyn Command,
+    config: &Config,
+    args: &ArgMatches,
+    format: &OutputFormat,
+) -> Result<(), Error> {
+    let file = match (command.input_file(args, config), format) {
+        (Ok(file), _) => file,
+        // Still one JSON object describing the failure, like any other run
+        (Err(_), OutputFormat::Json) => return run_json(command, config, args),
+        (Err(err), OutputFormat::Text) => return Err(err),
+    };
+    if file == STDIN_FILE_NAME {
+        return Err(SimpleError::new("Cannot watch stdin, use a file").into());
+    }
+
+    if let OutputFormat::Text = format {
+        println!(
+            "=============Watching {:} in {}=============",
+            command.name(),
+            file
+        );
+    }
+    let mut modified = None;
+    let mut previous: Option<RunReport> = None;
+    loop {
+        // Editors often replace the file when saving, so a missing file is waited out
+        let current = fs::metadata(&file)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if previous.is_none() || (current.is_some() && current != modified) {
+            modified = current;
+            let report = execute(command, config, args);
+            let change = previous
+                .as_ref()
+                .and_then(|previous| describe_change(previous, &report));
+
+            match format {
+                OutputFormat::Text => {
+                    match (&report.answer, &report.error) {
+                        (Some(answer), _) => print!("{}", answer),
+                        (None, error) => print!("error: {}", error.as_deref().unwrap_or("")),
+                    }
+                    match change {
+                        Some(change) => println!(" ({})", change),
+                        None => println!(),
+                    }
+                    if args.is_present("time") {
+                        println!(
+                            "parse: {:.3} ms, solve: {:.3} ms, total: {:.3} ms",
+                            report.parse_ms, report.solve_ms, report.elapsed_ms
+                        );
+                    }
+                }
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string(&WatchReport {
+                        report: &report,
+                        change,
+                    })?
+                ),
+            }
+            io::stdout().flush()?;
+            previous = Some(report);
+        }
+
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+fn describe_change(previous: &RunReport, report: &RunReport) -> Option<String> {
+    match (&previous.answer, &report.answer) {
+        (Some(previous), Some(answer)) => Some(
+            answer
+                .change_from(previous)
+                .unwrap_or_else(|| "unchanged".to_string()),
+        ),
+        (Some(previous), None) => Some(format!("was {}", previous)),
+        (None, Some(_)) => Some("previously failed".to_string()),
+        (None, None) => None,
+    }
+}
+
+fn run_all(registry: &Registry, config: &Config, format: &OutputFormat) -> Result<(), Error> {
+    let reports = registry
+        .commands()
+        .flat_map(|command| {
+            PRESETS.iter().filter_map(move |preset| {
+                command
+                    .sub_command()
+                    .get_matches_from_safe(vec![command.name(), preset])
+                    .ok()
+                    .map(|args| execute(command, config, &args))
+            })
+        })
+        .collect::<Vec<RunReport>>();
+
+    match format {
+        OutputFormat::Text => print_summary_table(&reports),
+        OutputFormat::Json => reports.iter().try_for_each(|report| {
+            serde_json::to_string(report).map(|line| {
+                println!("{}", line);
+            })
+        })?,
+    };
+
+    if reports.iter().any(|report| report.error.is_some()) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn execute(command: &dyn Command, config: &Config, args: &ArgMatches) -> RunReport {
+    let mut timings = Timings::new();
+    let start = Instant::now();
+    let (arguments, result) = match command.arguments(args, config) {
+        Ok(arguments) => (
+            arguments,
+            catch_panic(|| command.run(args, config, &mut timings)),
+        ),
+        Err(err) => (Value::Null, Err(err)),
+    };
+    let elapsed = start.elapsed();
+
+    let (answer, error) = match result {
+        Ok(answer) => (Some(answer), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+
+    RunReport {
+        command: command.name(),
+        preset: args.value_of(PRESET).map(|preset| preset.to_string()),
+        input: match (args.value_of(PRESET), args.value_of("file")) {
+            (Some(_), None) => Some(args.value_of("input").unwrap_or(DEFAULT_INPUT).to_string()),
+            _ => None,
+        },
+        arguments,
+        answer,
+        elapsed_ms: to_ms(elapsed),
+        parse_ms: to_ms(timings.parse_duration()),
+        solve_ms: to_ms(timings.solve_duration()),
+        error,
+    }
+}
+
+fn bench(registry: &Registry, config: &Config, args: &ArgMatches) -> Result<(), Error> {
+    let iterations = value_t_or_exit!(args.value_of("iterations"), usize);
+    if iterations == 0 {
+        return Err(SimpleError::new("Iterations must be at least 1").into());
+    }
+
+    let (command_name, command_args) = match args.subcommand() {
+        (command_name, Some(command_args)) => (command_name, command_args),
+        _ => return Err(SimpleError::new("No command to benchmark").into()),
+    };
+    let command = registry
+        .get(command_name)
+        .ok_or_else::<Error, _>(|| SimpleError::new("No valid subcommand found").into())?;
+    if input_files(command_args)?.is_some() {
+        return Err(SimpleError::new("Bench takes a single input file").into());
+    }
+    if iterations > 1 && command_args.value_of("file") == Some(STDIN_FILE_NAME) {
+        return Err(
+            SimpleError::new("Cannot read stdin more than once, use -n 1 or a file").into(),
+        );
+    }
+
+    let samples = (0..iterations)
+        .map(|_| {
+            let mut timings = Timings::new();
+            let start = Instant::now();
+            command
+                .run(command_args, config, &mut timings)
+                .map(|_| (timings, start.elapsed()))
+        })
+        .collect::<Result<Vec<(Timings, Duration)>, Error>>()?;
+
+    let report = BenchReport {
+        command: command.name(),
+        preset: command_args
+            .value_of(PRESET)
+            .map(|preset| preset.to_string()),
+        arguments: command.arguments(command_args, config)?,
+        iterations,
+        parse: DurationStats::from_durations(
+            samples
+                .iter()
+                .map(|(timings, _)| timings.parse_duration())
+                .collect(),
+        ),
+        solve: DurationStats::from_durations(
+            samples
+                .iter()
+                .map(|(timings, _)| timings.solve_duration())
+                .collect(),
+        ),
+        total: DurationStats::from_durations(samples.iter().map(|(_, elapsed)| *elapsed).collect()),
+    };
+
+    match value_t_or_exit!(command_args.value_of("format"), OutputFormat) {
+        OutputFormat::Text => {
+            println!(
+                "=============Benchmarking {:} ({} iterations)=============",
+                report.command, report.iterations
+            );
+            print_table(
+                &["Phase", "Min (ms)", "Median (ms)", "Max (ms)"],
+                &[
+                    ("parse", &report.parse),
+                    ("solve", &report.solve),
+                    ("total", &report.total),
+                ]
+                .iter()
+                .map(|(phase, stats)| {
+                    vec![
+                        phase.to_string(),
+                        format!("{:.3}", stats.min_ms),
+                        format!("{:.3}", stats.median_ms),
+                        format!("{:.3}", stats.max_ms),
+                    ]
+                })
+                .collect::<Vec<Vec<String>>>(),
+            );
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string(&report)?),
+    };
+
+    Ok(())
+}
+
+fn generate_sub_command(command: &dyn Command) -> App<'static, 'static> {
+    SubCommand::with_name(command.name())
+        .about(command.generate_about())
+        .arg(
+            Arg::with_name("size")
+                .short("n")
+                .long("size")
+                .help("Main size of the input, usually the number of lines.")
+                .takes_value(true)
+                .default_value("1000"),
+        )
+        .arg(
+            Arg::with_name("width")
+                .long("width")
+                .help("Width of generated grids. Defaults to the size.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("depth")
+                .long("depth")
+                .help("Nesting depth for inputs that nest.")
+                .takes_value(true)
+                .default_value("6"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .help(
+                    "Seed for the random number generator. When missing a seed is picked from \
+                    the clock and printed to stderr.",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .help("File to write the input to. Defaults to stdout.")
+                .takes_value(true),
+        )
+}
+
+fn generate(registry: &Registry, args: &ArgMatches) -> Result<(), Error> {
+    let (command_name, command_args) = match args.subcommand() {
+        (command_name, Some(command_args)) => (command_name, command_args),
+        _ => return Err(SimpleError::new("No command to generate input for").into()),
+    };
+    let command = registry
+        .get(command_name)
+        .ok_or_else::<Error, _>(|| SimpleError::new("No valid subcommand found").into())?;
+
+    let size = value_t_or_exit!(command_args.value_of("size"), usize);
+    let options = GenerateOptions {
+        size,
+        width: match command_args.value_of("width") {
+            Some(_) => value_t_or_exit!(command_args.value_of("width"), usize),
+            None => size,
+        },
+        depth: value_t_or_exit!(command_args.value_of("depth"), usize),
+    };
+    let seed = match command_args.value_of("seed") {
+        Some(_) => value_t_or_exit!(command_args.value_of("seed"), u64),
+        None => {
+            let seed = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+            eprintln!("seed: {}", seed);
+            seed
+        }
+    };
+
+    let mut output: Box<dyn Write> = match command_args.value_of("output") {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|err| {
+            Error::from(err).context(format!("Could not create {}", path))
+        })?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    command.generate(&options, &mut ChaCha8Rng::seed_from_u64(seed), &mut output)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn repl(registry: &Registry, config: &Config, args: &ArgMatches) -> Result<(), Error> {
+    let (command_name, command_args) = match args.subcommand() {
+        (command_name, Some(command_args)) => (command_name, command_args),
+        _ => return Err(SimpleError::new("No command to load").into()),
+    };
+    let command = registry
+        .get(command_name)
+        .ok_or_else::<Error, _>(|| SimpleError::new("No valid subcommand found").into())?;
+    if input_files(command_args)?.is_some() {
+        return Err(SimpleError::new("The repl takes a single input file").into());
+    }
+    if command_args.value_of("file") == Some(STDIN_FILE_NAME) {
+        return Err(SimpleError::new("The repl reads commands from stdin, use a file").into());
+    }
+
+    let stdin = io::stdin();
+    command.repl(command_args, config, &mut stdin.lock(), &mut io::stdout())
+}
+
+fn to_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn verify(registry: &Registry, config: &Config, format: &OutputFormat) -> Result<(), Error> {
+    let verifications = registry
+        .commands()
+        .map(|command| {
+            read_expected_answers(command, config).map(|expected_answers| {
+                expected_answers
+                    .into_iter()
+                    .map(|expected| {
+                        let report = command
+                            .sub_command()
+                            .get_matches_from_safe(vec![
+                                command.name(),
+                                &expected.preset,
+                                "--input",
+                                &expected.input,
+                            ])
+                            .map(|args| execute(command, config, &args))
+                            .unwrap_or_else(|_| {
+                                RunReport::failed(
+                                    command,
+                                    Some(&expected.preset),
+                                    Some(&expected.input),
+                                    SimpleError::new(format!(
+                                        "No preset named {}",
+                                        expected.preset
+                                    ))
+                                    .into(),
+                                )
+                            });
+                        let matches = report
+                            .answer
+                            .as_ref()
+                            .map(|answer| answer.to_string() == expected.answer)
+                            .unwrap_or(false);
+
+                        Verification {
+                            report,
+                            expected: expected.answer,
+                            matches,
+                        }
+                    })
+                    .collect::<Vec<Verification>>()
+            })
+        })
+        .collect::<Result<Vec<Vec<Verification>>, Error>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<Verification>>();
+
+    match format {
+        OutputFormat::Text => print_verifications(&verifications),
+        OutputFormat::Json => verifications.iter().try_for_each(|verification| {
+            serde_json::to_string(verification).map(|line| {
+                println!("{}", line);
+            })
+        })?,
+    };
+
+    if verifications
         .iter()
-        .fold(app, |app, command| app.subcommand(command.sub_command()))
-        .get_matches();
+        .any(|verification| !verification.matches)
+    {
+        std::process::exit(1);
+    }
+    Ok(())
+}
 
-    let sub_commands: HashMap<&str, &Command> = COMMANDS
+fn read_expected_answers(
+    command: &dyn Command,
+    config: &Config,
+) -> Result<Vec<ExpectedAnswer>, Error> {
+    let file_name = config
+        .answers_file(command.day())
+        .to_string_lossy()
+        .into_owned();
+
+    if !Path::new(&file_name).exists() {
+        return Ok(Vec::new());
+    }
+
+    file_to_lines(&file_name).and_then(|lines| {
+        parse_lines(
+            lines.iter().filter(|line| !line.trim().is_empty()),
+            parse_expected_answer,
+        )
+    })
+}
+
+fn parse_expected_answer(line: &str) -> Result<ExpectedAnswer, Error> {
+    line.split_once(':')
+        .and_then(|(key, answer)| {
+            let mut key = key.split_whitespace();
+            match (key.next(), key.next(), key.next()) {
+                (Some(preset), input, None) => Some(ExpectedAnswer {
+                    preset: preset.to_string(),
+                    input: input.unwrap_or(DEFAULT_INPUT).to_string(),
+                    answer: answer.trim().to_string(),
+                }),
+                _ => None,
+            }
+        })
+        .ok_or_else(|| {
+            SimpleError::new(format!(
+                "Expected answer should be preset [input]: answer, got {}",
+                line
+            ))
+            .into()
+        })
+}
+
+fn print_verifications(verifications: &[Verification]) {
+    print_table(
+        &["Command", "Preset", "Input", "Expected", "Actual", "Status"],
+        &verifications
+            .iter()
+            .map(|verification| {
+                vec![
+                    verification.report.command.to_string(),
+                    verification.report.preset.clone().unwrap_or_default(),
+                    verification.report.input.clone().unwrap_or_default(),
+                    verification.expected.clone(),
+                    report_outcome(&verification.report.answer, &verification.report.error),
+                    if verification.matches {
+                        "ok".to_string()
+                    } else {
+                        "MISMATCH".to_string()
+                    },
+                ]
+            })
+            .collect::<Vec<Vec<String>>>(),
+    );
+
+    verifications
         .iter()
-        .map(|command| (command.name(), command))
-        .collect();
+        .filter(|verification| !verification.matches)
+        .for_each(|verification| {
+            println!();
+            println!(
+                "{} {} --input {}",
+                verification.report.command,
+                verification.report.preset.clone().unwrap_or_default(),
+                verification.report.input.clone().unwrap_or_default()
+            );
+            println!("- {}", verification.expected);
+            println!(
+                "+ {}",
+                report_outcome(&verification.report.answer, &verification.report.error)
+            );
+        });
+}
 
-    if let (command_name, Some(args)) = matches.subcommand() {
-        sub_commands
-            .get(command_name)
-            .ok_or_else::<Error, _>(|| SimpleError::new("No valid subcommand found").into())
-            .and_then(|command| {
-                println!("=============Running {:}=============", command.name());
-                command.run(args)
+fn print_summary_table(reports: &[RunReport]) {
+    print_table(
+        &[
+            "Command",
+            "Preset",
+            "Answer",
+            "Parse (ms)",
+            "Solve (ms)",
+            "Time (ms)",
+        ],
+        &reports
+            .iter()
+            .map(|report| {
+                vec![
+                    report.command.to_string(),
+                    report.preset.clone().unwrap_or_default(),
+                    report_outcome(&report.answer, &report.error),
+                    format!("{:.3}", report.parse_ms),
+                    format!("{:.3}", report.solve_ms),
+                    format!("{:.3}", report.elapsed_ms),
+                ]
             })
-    } else {
-        Err(SimpleError::new("No arguments found").into())
+            .collect::<Vec<Vec<String>>>(),
+    );
+}
+
+fn print_file_table(reports: &[FileReport]) {
+    print_table(
+        &["File", "Answer", "Parse (ms)", "Solve (ms)", "Time (ms)"],
+        &reports
+            .iter()
+            .map(|report| {
+                vec![
+                    report.file.clone(),
+                    report_outcome(&report.answer, &report.error),
+                    format!("{:.3}", report.parse_ms),
+                    format!("{:.3}", report.solve_ms),
+                    format!("{:.3}", report.elapsed_ms),
+                ]
+            })
+            .collect::<Vec<Vec<String>>>(),
+    );
+}
+
+fn report_outcome(answer: &Option<Answer>, error: &Option<String>) -> String {
+    match (answer, error) {
+        (_, Some(error)) => format!("error: {}", error),
+        (Some(answer), None) => answer.to_string(),
+        (None, None) => String::new(),
+    }
+}
+
+fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let widths = rows.iter().fold(
+        header.iter().map(|cell| cell.len()).collect::<Vec<usize>>(),
+        |mut widths, row| {
+            row.iter().enumerate().for_each(|(index, cell)| {
+                widths[index] = widths[index].max(cell.len());
+            });
+            widths
+        },
+    );
+
+    let print_row = |row: Vec<&str>| {
+        println!(
+            "{}",
+            row.iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<String>>()
+                .join(" | ")
+                .trim_end()
+        );
+    };
+
+    print_row(header.to_vec());
+    println!(
+        "{}",
+        widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<String>>()
+            .join("-+-")
+    );
+    rows.iter()
+        .for_each(|row| print_row(row.iter().map(|cell| cell.as_str()).collect()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_bad_file_becomes_an_error_row_between_good_ones() {
+        let bad_file = std::env::temp_dir().join("adventofcode2020-bad-schedule.txt");
+        fs::write(&bad_file, "939\nx,x\n").unwrap();
+        let files = vec![
+            format!("{}/day13/sample.txt", env!("CARGO_MANIFEST_DIR")),
+            bad_file.to_string_lossy().into_owned(),
+            format!("{}/day13/sample.txt", env!("CARGO_MANIFEST_DIR")),
+        ];
+        let registry = registry();
+        let command = registry.get("shuttle-search").unwrap();
+        let args = command.sub_command().get_matches_from(vec![
+            "shuttle-search",
+            "-f",
+            &files[0],
+            "-f",
+            &files[1],
+            "-f",
+            &files[2],
+        ]);
+
+        let reports = execute_files(command, &Config::default(), &args, &files);
+
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| (report.file.as_str(), report.error.is_some()))
+                .collect::<Vec<(&str, bool)>>(),
+            vec![
+                (files[0].as_str(), false),
+                (files[1].as_str(), true),
+                (files[2].as_str(), false)
+            ]
+        );
+        assert_eq!(reports[0].answer, Some(Answer::Integer(295)));
+    }
+
+    #[test]
+    fn json_errors_are_reported_for_the_named_command() {
+        let registry = registry();
+        let raw_args = |args: &[&str]| {
+            iter::once(BIN_NAME)
+                .chain(args.iter().copied())
+                .map(|arg| arg.to_string())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            json_command(
+                &registry,
+                &raw_args(&["--format", "json", "rain-risk", "-f", "x", "-d", "bogus"])
+            )
+            .map(|command| command.name()),
+            Some("rain-risk")
+        );
+        assert_eq!(
+            json_command(
+                &registry,
+                &raw_args(&["rain-risk", "--format=json", "part1"])
+            )
+            .map(|command| command.name()),
+            Some("rain-risk")
+        );
+        assert!(json_command(
+            &registry,
+            &raw_args(&["rain-risk", "-f", "x", "-d", "bogus"])
+        )
+        .is_none());
+        assert!(json_command(&registry, &raw_args(&["--format", "json", "verify"])).is_none());
     }
 }
@@ -1,28 +1,35 @@
-use crate::lib::{default_sub_command, file_to_string, parse_lines_borrowed, parse_usize, Command};
+use crate::{
+    default_sub_command, flag_or_preset, parse_usize, preset_arg, random_letter, value_or_preset,
+    GenerateOptions, InputReader, ParseError, Puzzle,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches};
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag, take, take_until},
+    bytes::complete::{is_not, take},
     character::complete,
     combinator::{all_consuming, map_parser, map_res},
-    multi::{many0, separated_list1},
-    sequence::{preceded, separated_pair, terminated, tuple},
+    multi::separated_list1,
+    sequence::{preceded, separated_pair, tuple},
+    IResult,
 };
-use simple_error::SimpleError;
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
 use std::str::FromStr;
+use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const PASSPORT_PROCESSING: Command = Command::new(sub_command, "passport-processing", run);
+pub struct PassportProcessing;
 
-#[derive(Debug)]
-struct PassportProcessingArgs {
-    file: String,
-    verify_fields: bool,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassportProcessingArgs {
+    pub file: String,
+    pub verify_fields: bool,
 }
 
-#[derive(Debug, EnumVariantNames, EnumString)]
-enum HeightUnit {
+#[derive(Debug, Clone, EnumVariantNames, EnumString)]
+pub enum HeightUnit {
     #[strum(serialize = "cm")]
     Centimeters,
 
@@ -30,15 +37,15 @@ enum HeightUnit {
     Inches,
 }
 
-#[derive(Debug)]
-struct Height {
-    height: usize,
-    unit: HeightUnit,
+#[derive(Debug, Clone)]
+pub struct Height {
+    pub height: usize,
+    pub unit: HeightUnit,
 }
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+#[derive(Debug, Clone, EnumString, EnumVariantNames)]
 #[strum(serialize_all = "kebab_case")]
-enum EyeColor {
+pub enum EyeColor {
     Amb,
     Blu,
     Brn,
@@ -48,16 +55,16 @@ enum EyeColor {
     Oth,
 }
 
-#[derive(Debug)]
-struct Passport {
-    byr: Option<usize>,
-    iyr: Option<usize>,
-    eyr: Option<usize>,
-    hgt: Option<Height>,
-    hcl: Option<String>,
-    ecl: Option<EyeColor>,
-    pid: Option<String>,
-    cid: Option<String>,
+#[derive(Debug, Clone)]
+pub struct Passport {
+    pub byr: Option<usize>,
+    pub iyr: Option<usize>,
+    pub eyr: Option<usize>,
+    pub hgt: Option<Height>,
+    pub hcl: Option<String>,
+    pub ecl: Option<EyeColor>,
+    pub pid: Option<String>,
+    pub cid: Option<String>,
 }
 
 impl Passport {
@@ -75,84 +82,125 @@ impl Passport {
     }
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &PASSPORT_PROCESSING,
-        "Takes a passport file and validates each passport within. Returns the number of valid \
-    passports in the input.",
-        "Path to the input file. Input should be passports seperated by a blank line. \
-    Passport fields should be key:value and seperated by a space or a newline.",
-    )
-    .arg(
-        Arg::with_name("verify-fields").short("v").help(
-            "When passed, verifies the field value of the passport instead of just presence.",
-        ),
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about("Validates the default input but does not validate field values")
-            .version("1.0.0"),
-    )
-    .subcommand(
-        SubCommand::with_name("part2")
-            .about("Validates the default input and validates field values")
-            .version("1.0.0"),
-    )
-}
+impl Puzzle for PassportProcessing {
+    type Args = PassportProcessingArgs;
+    type Input = usize;
+    type Answer = usize;
+
+    const NAME: &'static str = "passport-processing";
+    const DAY: usize = 4;
+    const GENERATE_ABOUT: &'static str =
+        "Writes size passports. Fields are sometimes missing and about a tenth of the values are malformed.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Takes a passport file and validates each passport within. Returns the number of valid \
+        passports in the input.",
+            "Path to the input file. Input should be passports seperated by a blank line. \
+        Passport fields should be key:value and seperated by a space or a newline.",
+        )
+        .arg(
+            Arg::with_name("verify-fields").short("v").help(
+                "When passed, verifies the field value of the passport instead of just presence.",
+            ),
+        )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Validates the default input but does not validate field values\n\n\
+            part2: Validates the default input and validates field values\n",
+        ))
+    }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let passport_processing_arguments = match arguments.subcommand_name() {
-        Some("part1") => PassportProcessingArgs {
-            file: "day4/input.txt".to_string(),
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&PassportProcessingArgs>,
+    ) -> Result<PassportProcessingArgs, Error> {
+        Ok(PassportProcessingArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            verify_fields: flag_or_preset(
+                arguments,
+                "verify-fields",
+                preset.map(|preset| &preset.verify_fields),
+            ),
+        })
+    }
+
+    fn part1_args(&self, file: String) -> PassportProcessingArgs {
+        PassportProcessingArgs {
+            file,
             verify_fields: false,
-        },
-        Some("part2") => PassportProcessingArgs {
-            file: "day4/input.txt".to_string(),
-            verify_fields: true,
-        },
-        _ => PassportProcessingArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            verify_fields: arguments.is_present("verify-fields"),
-        },
-    };
+        }
+    }
 
-    process_passports(&passport_processing_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
+    fn part2_args(&self, file: String) -> Option<PassportProcessingArgs> {
+        Some(PassportProcessingArgs {
+            file,
+            verify_fields: true,
         })
-        .map(|_| ())
+    }
+
+    fn file<'a>(&self, arguments: &'a PassportProcessingArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: PassportProcessingArgs, file: String) -> PassportProcessingArgs {
+        PassportProcessingArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        arguments: &PassportProcessingArgs,
+    ) -> Result<usize, Error> {
+        parse_input(input, arguments.verify_fields)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn reuses_input(
+        &self,
+        parsed_with: &PassportProcessingArgs,
+        arguments: &PassportProcessingArgs,
+    ) -> bool {
+        parsed_with.verify_fields == arguments.verify_fields
+    }
+
+    fn solve(
+        &self,
+        valid_passports: usize,
+        _arguments: &PassportProcessingArgs,
+    ) -> Result<usize, Error> {
+        Ok(valid_passports)
+    }
 }
 
-fn process_passports(arguments: &PassportProcessingArgs) -> Result<usize, Error> {
-    file_to_string(&arguments.file)
-        .and_then(|file| parse_passports(&file.to_string(), arguments.verify_fields))
-        .map(|passports| {
-            passports
-                .into_iter()
-                .filter(|passport| validate_passport(&passport))
-                .count()
-        })
+/// Counts the valid passports as they are read rather than keeping them.
+pub fn parse_input(input: &mut InputReader, verify_fields: bool) -> Result<usize, Error> {
+    input.fold_records(
+        0,
+        |record| parse_passport_record(record, verify_fields),
+        |valid_passports, passport| valid_passports + validate_passport(&passport) as usize,
+    )
 }
 
-fn parse_passports(file: &str, verify_fields: bool) -> Result<Vec<Passport>, Error> {
-    many0(terminated(take_until("\n\n"), tag("\n\n")))(file)
-        .and_then(|(_, passport_entries)| {
-            parse_lines_borrowed(
-                passport_entries,
-                separated_list1(
-                    alt((complete::char(' '), complete::char('\n'))),
-                    separated_pair(complete::alphanumeric1, complete::char(':'), is_not(" \n")),
-                ),
-            )
-            .map(|parse_results| {
-                parse_results
-                    .into_iter()
-                    .map(|(_, result)| result)
-                    .map(|passport_candidate| parse_passport(passport_candidate, verify_fields))
-                    .collect()
-            })
-        })
-        .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse failure").into())
+fn parse_passport_record(record: &str, verify_fields: bool) -> Result<Passport, Error> {
+    all_consuming(separated_list1(
+        alt((complete::char(' '), complete::char('\n'))),
+        separated_pair(complete::alphanumeric1, complete::char(':'), is_not(" \n")),
+    ))(record)
+    .map(|(_, passport_candidate)| parse_passport(passport_candidate, verify_fields))
+    .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+        ParseError::from_nom(record, err, "fields like \"key:value\" separated by spaces").into()
+    })
 }
 
 fn parse_passport(passport_candidate: Vec<(&str, &str)>, verify_fields: bool) -> Passport {
@@ -220,38 +268,29 @@ fn parse_passport(passport_candidate: Vec<(&str, &str)>, verify_fields: bool) ->
     passport
 }
 
+/// An invalid field value isn't an error, the field is just left out of the passport.
+fn field_value<O>(result: IResult<&str, O>) -> Option<O> {
+    result.ok().map(|(_, value)| value)
+}
+
 fn parse_byr(input: &str) -> Option<usize> {
-    all_consuming(parse_usize)(input)
-        .map(|(_, value)| value)
-        .ok()
-        .filter(|value| value >= &1920 && value <= &2002)
+    field_value(all_consuming(parse_usize)(input)).filter(|value| (1920..=2002).contains(value))
 }
 
 fn parse_iyr(input: &str) -> Option<usize> {
-    all_consuming(parse_usize)(input)
-        .map(|(_, value)| value)
-        .ok()
-        .filter(|value| value >= &2010 && value <= &2020)
+    field_value(all_consuming(parse_usize)(input)).filter(|value| (2010..=2020).contains(value))
 }
 
 fn parse_eyr(input: &str) -> Option<usize> {
-    all_consuming(parse_usize)(input)
-        .map(|(_, value)| value)
-        .ok()
-        .filter(|value| value >= &2020 && value <= &2030)
+    field_value(all_consuming(parse_usize)(input)).filter(|value| (2020..=2030).contains(value))
 }
 
 fn parse_hgt(input: &str) -> Option<Height> {
-    all_consuming(tuple((
+    field_value(all_consuming(tuple((
         parse_usize,
         map_res(complete::alpha1, HeightUnit::from_str),
-    )))(input)
-    .map_err(|_| SimpleError::new("Parse Error"))
-    .map(|(_, (height, unit))| Height {
-        height: height,
-        unit: unit,
-    })
-    .ok()
+    )))(input))
+    .map(|(height, unit)| Height { height, unit })
     .filter(|height| match height.unit {
         HeightUnit::Centimeters => height.height >= 150 && height.height <= 193,
         HeightUnit::Inches => height.height >= 59 && height.height <= 76,
@@ -259,30 +298,26 @@ fn parse_hgt(input: &str) -> Option<Height> {
 }
 
 fn parse_hcl(input: &str) -> Option<String> {
-    all_consuming(preceded(
+    field_value(all_consuming(preceded(
         complete::char('#'),
-        map_parser(take(6usize), complete::hex_digit1),
-    ))(input)
-    .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse Error"))
-    .map(|(_, value)| value.to_string())
-    .ok()
+        map_parser(take(6usize), all_consuming(complete::hex_digit1)),
+    ))(input))
+    .map(|value| value.to_string())
 }
 
 fn parse_ecl(input: &str) -> Option<EyeColor> {
-    all_consuming(map_res(complete::alpha1, EyeColor::from_str))(input)
-        .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse Error"))
-        .map(|(_, eye_color)| eye_color)
-        .ok()
+    field_value(all_consuming(map_res(complete::alpha1, EyeColor::from_str))(input))
 }
 
 fn parse_pid(input: &str) -> Option<String> {
-    all_consuming(map_parser(take(9usize), complete::digit1))(input)
-        .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse Error"))
-        .map(|(_, pid)| pid.to_string())
-        .ok()
+    field_value(all_consuming(map_parser(
+        take(9usize),
+        all_consuming(complete::digit1),
+    ))(input))
+    .map(|pid| pid.to_string())
 }
 
-fn validate_passport(passport: &Passport) -> bool {
+pub fn validate_passport(passport: &Passport) -> bool {
     passport.byr.is_some()
         && passport.iyr.is_some()
         && passport.eyr.is_some()
@@ -291,3 +326,135 @@ fn validate_passport(passport: &Passport) -> bool {
         && passport.ecl.is_some()
         && passport.pid.is_some()
 }
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    (0..options.size).try_for_each(|index| {
+        let mut fields = vec![
+            ("byr", rng.gen_range(1900..=2010).to_string()),
+            ("iyr", rng.gen_range(2005..=2025).to_string()),
+            ("eyr", rng.gen_range(2015..=2035).to_string()),
+            ("hgt", generate_hgt(rng)),
+            ("hcl", generate_hcl(rng)),
+            ("ecl", generate_ecl(rng)),
+            ("pid", generate_pid(rng)),
+            ("cid", rng.gen_range(100..=350).to_string()),
+        ];
+        fields.retain(|(key, _)| rng.gen_bool(if *key == "cid" { 0.5 } else { 0.9 }));
+        if fields.is_empty() {
+            fields.push(("byr", rng.gen_range(1900..=2010).to_string()));
+        }
+        fields.shuffle(rng);
+
+        if index > 0 {
+            writeln!(output)?;
+        }
+        fields
+            .iter()
+            .enumerate()
+            .try_for_each(|(field, (key, value))| {
+                let separator = match field {
+                    0 => "",
+                    _ if rng.gen_bool(0.3) => "\n",
+                    _ => " ",
+                };
+                write!(output, "{}{}:{}", separator, key, value)
+            })?;
+        writeln!(output)
+    })?;
+    Ok(())
+}
+
+fn generate_hgt(rng: &mut dyn RngCore) -> String {
+    match rng.gen_range(0..10) {
+        0 => rng.gen_range(50..=200).to_string(),
+        1..=5 => format!("{}cm", rng.gen_range(140..=200)),
+        _ => format!("{}in", rng.gen_range(50..=80)),
+    }
+}
+
+fn generate_hcl(rng: &mut dyn RngCore) -> String {
+    let color = (0..6)
+        .map(|_| {
+            if rng.gen_bool(0.98) {
+                std::char::from_digit(rng.gen_range(0..16), 16).unwrap_or('0')
+            } else {
+                random_letter(rng)
+            }
+        })
+        .collect::<String>();
+
+    if rng.gen_bool(0.95) {
+        format!("#{}", color)
+    } else {
+        color
+    }
+}
+
+fn generate_ecl(rng: &mut dyn RngCore) -> String {
+    if rng.gen_bool(0.9) {
+        EyeColor::VARIANTS[rng.gen_range(0..EyeColor::VARIANTS.len())].to_string()
+    } else {
+        (0..3).map(|_| random_letter(rng)).collect()
+    }
+}
+
+fn generate_pid(rng: &mut dyn RngCore) -> String {
+    let length = match rng.gen_range(0..20) {
+        0 => 8,
+        1 => 10,
+        _ => 9,
+    };
+    (0..length)
+        .map(|_| std::char::from_digit(rng.gen_range(0..10), 10).unwrap_or('0'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_input;
+
+    #[test]
+    fn parses_passport_record() {
+        let passport =
+            parse_passport_record("ecl:gry pid:860033327\nhcl:#fffffd cid:147", false).unwrap();
+
+        assert!(passport.ecl.is_some());
+        assert_eq!(passport.pid.as_deref(), Some("860033327"));
+        assert_eq!(passport.cid.as_deref(), Some("147"));
+        assert!(passport.byr.is_none());
+        assert!(parse_passport_record("ecl:gry pid", false).is_err());
+    }
+
+    #[test]
+    fn validates_field_values() {
+        assert!(parse_byr("2002").is_some());
+        assert!(parse_byr("2003").is_none());
+        assert!(parse_hgt("60in").is_some());
+        assert!(parse_hgt("190cm").is_some());
+        assert!(parse_hgt("190in").is_none());
+        assert!(parse_hgt("190").is_none());
+        assert!(parse_hcl("#123abc").is_some());
+        assert!(parse_hcl("#123abz").is_none());
+        assert!(parse_hcl("123abc").is_none());
+        assert!(parse_ecl("brn").is_some());
+        assert!(parse_ecl("wat").is_none());
+        assert!(parse_pid("000000001").is_some());
+        assert!(parse_pid("0123456789").is_none());
+        assert!(parse_pid("00000000a").is_none());
+    }
+
+    #[test]
+    fn solves_samples() {
+        let count_valid =
+            |name, verify_fields| parse_input(&mut sample_input(4, name), verify_fields).unwrap();
+
+        assert_eq!(count_valid("sample", false), 2);
+        assert_eq!(count_valid("valid", true), 4);
+        assert_eq!(count_valid("invalid", true), 0);
+    }
+}
@@ -1,106 +1,152 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_lines, parse_usize, Command};
+use crate::{
+    default_sub_command, parse_usize, preset_arg, random_letter, value_or_preset, GenerateOptions,
+    InputReader, ParseError, Puzzle, PRESET,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches};
 use nom::{
     bytes::complete::{tag, take, take_while1},
     character::complete,
     combinator::map_parser,
     sequence::{preceded, tuple},
 };
-use simple_error::SimpleError;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const PASSWORD_PHILOSOPHY: Command = Command::new(sub_command, "password-philosophy", run);
+pub struct PasswordPhilosophy;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordPhilosophyArgs {
+    pub file: String,
+    pub password_policy: PasswordPolicy,
+}
 
-struct PasswordPhilosophyArgs {
-    file: String,
-    password_policy: PasswordPolicy,
+#[derive(Debug, Clone)]
+pub struct PasswordLine {
+    pub first: usize,
+    pub second: usize,
+    pub character: char,
+    pub password: String,
 }
 
-#[derive(Debug)]
-struct PasswordLine {
-    first: usize,
-    second: usize,
-    character: char,
-    password: String,
+/// How many passwords each policy accepts, counted as the input is read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidPasswords {
+    pub required_count: usize,
+    pub required_positions: usize,
 }
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+#[derive(Debug, Clone, EnumString, EnumVariantNames, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab_case")]
-enum PasswordPolicy {
+#[serde(rename_all = "kebab-case")]
+pub enum PasswordPolicy {
     RequiredCount,
     RequiredPositions,
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(&PASSWORD_PHILOSOPHY, "Takes a list of password key/password pairs and returns the number of valid passwords.",
-    "Path to the input file. Input should be newline delimited and each line \
-    should have the form: {unsigned int}-{unsigned int} {character}: {password}")
-        .arg(
-            Arg::with_name("policy")
-                .short("p")
-                .help("Password policy to use to validate the password. Valid policies are as follows:\n\n\
-                required-count: The first {unsigned int} is the minimum number of {character} required in \
-                the password. While the second {unsigned int} is the maximum.\n\n\
-                required-positions: Each {unsigned int} is a 1 based index where exactly one of those indexes \
-                contains the {character}.")
-                .takes_value(true)
-                .possible_values(&PasswordPolicy::VARIANTS)
-                .required(true),
-        )
-        .subcommand(
-            SubCommand::with_name("part1")
-                .about(
-                    "Validates the default input with the required-count policy",
-                )
-                .version("1.0.0"),
-        )
-        .subcommand(
-            SubCommand::with_name("part2")
-                .about(
-                    "Validates the default input with the required-positions policy",
-                )
-                .version("1.0.0"),
-        )
-}
+impl Puzzle for PasswordPhilosophy {
+    type Args = PasswordPhilosophyArgs;
+    type Input = ValidPasswords;
+    type Answer = usize;
+
+    const NAME: &'static str = "password-philosophy";
+    const DAY: usize = 2;
+    const GENERATE_ABOUT: &'static str =
+        "Writes size password lines, roughly a third of the password is the policy's character.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(Self::NAME, "Takes a list of password key/password pairs and returns the number of valid passwords.",
+        "Path to the input file. Input should be newline delimited and each line \
+        should have the form: {unsigned int}-{unsigned int} {character}: {password}")
+            .arg(
+                Arg::with_name("policy")
+                    .short("p")
+                    .help("Password policy to use to validate the password. Valid policies are as follows:\n\n\
+                    required-count: The first {unsigned int} is the minimum number of {character} required in \
+                    the password. While the second {unsigned int} is the maximum.\n\n\
+                    required-positions: Each {unsigned int} is a 1 based index where exactly one of those indexes \
+                    contains the {character}.")
+                    .takes_value(true)
+                    .possible_values(PasswordPolicy::VARIANTS)
+                    .required_unless(PRESET),
+            )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Validates the default input with the required-count policy\n\n\
+            part2: Validates the default input with the required-positions policy\n",
+        ))
+    }
+
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&PasswordPhilosophyArgs>,
+    ) -> Result<PasswordPhilosophyArgs, Error> {
+        Ok(PasswordPhilosophyArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            password_policy: value_or_preset(
+                arguments,
+                "policy",
+                preset.map(|preset| &preset.password_policy),
+            )?,
+        })
+    }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let password_philosophy_arguments = match arguments.subcommand_name() {
-        Some("part1") => PasswordPhilosophyArgs {
-            file: "day2/input.txt".to_string(),
+    fn part1_args(&self, file: String) -> PasswordPhilosophyArgs {
+        PasswordPhilosophyArgs {
+            file,
             password_policy: PasswordPolicy::RequiredCount,
-        },
-        Some("part2") => PasswordPhilosophyArgs {
-            file: "day2/input.txt".to_string(),
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<PasswordPhilosophyArgs> {
+        Some(PasswordPhilosophyArgs {
+            file,
             password_policy: PasswordPolicy::RequiredPositions,
-        },
-        _ => PasswordPhilosophyArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            password_policy: value_t_or_exit!(arguments.value_of("policy"), PasswordPolicy),
-        },
-    };
-
-    let password_validator = match password_philosophy_arguments.password_policy {
-        PasswordPolicy::RequiredCount => is_min_max_char_password_valid,
-        PasswordPolicy::RequiredPositions => is_position_char_password_valid,
-    };
-
-    file_to_lines(&password_philosophy_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_password_line))
-        .map(|password_lines| {
-            password_lines
-                .into_iter()
-                .filter(password_validator)
-                .count()
         })
-        .map(|result| {
-            println!("{:#?}", result);
+    }
+
+    fn file<'a>(&self, arguments: &'a PasswordPhilosophyArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: PasswordPhilosophyArgs, file: String) -> PasswordPhilosophyArgs {
+        PasswordPhilosophyArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &PasswordPhilosophyArgs,
+    ) -> Result<ValidPasswords, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(
+        &self,
+        valid_passwords: ValidPasswords,
+        arguments: &PasswordPhilosophyArgs,
+    ) -> Result<usize, Error> {
+        Ok(match arguments.password_policy {
+            PasswordPolicy::RequiredCount => valid_passwords.required_count,
+            PasswordPolicy::RequiredPositions => valid_passwords.required_positions,
         })
-        .map(|_| ())
+    }
 }
 
-fn is_min_max_char_password_valid(password_line: &PasswordLine) -> bool {
+pub fn is_min_max_char_password_valid(password_line: &PasswordLine) -> bool {
     let instances = password_line
         .password
         .chars()
@@ -109,7 +155,7 @@ fn is_min_max_char_password_valid(password_line: &PasswordLine) -> bool {
     instances >= password_line.first && instances <= password_line.second
 }
 
-fn is_position_char_password_valid(password_line: &PasswordLine) -> bool {
+pub fn is_position_char_password_valid(password_line: &PasswordLine) -> bool {
     password_line
         .password
         .chars()
@@ -123,7 +169,20 @@ fn is_position_char_password_valid(password_line: &PasswordLine) -> bool {
         == 1
 }
 
-fn parse_password_line(line: &String) -> Result<PasswordLine, Error> {
+pub fn parse_input(input: &mut InputReader) -> Result<ValidPasswords, Error> {
+    input.fold_lines(
+        ValidPasswords::default(),
+        parse_password_line,
+        |valid_passwords, password_line| ValidPasswords {
+            required_count: valid_passwords.required_count
+                + is_min_max_char_password_valid(&password_line) as usize,
+            required_positions: valid_passwords.required_positions
+                + is_position_char_password_valid(&password_line) as usize,
+        },
+    )
+}
+
+fn parse_password_line(line: &str) -> Result<PasswordLine, Error> {
     tuple((
         parse_usize,
         preceded(complete::char('-'), parse_usize),
@@ -134,10 +193,70 @@ fn parse_password_line(line: &String) -> Result<PasswordLine, Error> {
         preceded(tag(": "), take_while1(|_| true)),
     ))(line)
     .map(|(_, (first, second, character, password))| PasswordLine {
-        first: first,
-        second: second,
-        character: character,
+        first,
+        second,
+        character,
         password: password.to_string(),
     })
-    .map_err(|_| SimpleError::new("Parse failure").into())
+    .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+        ParseError::from_nom(line, err, "a policy and password like \"1-3 a: abcde\"").into()
+    })
+}
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    (0..options.size).try_for_each(|_| {
+        let first = rng.gen_range(1..=8);
+        let second = rng.gen_range(first + 1..=first + 8);
+        let character = random_letter(rng);
+        let length = rng.gen_range(first..=second + 4);
+        let password = (0..length)
+            .map(|_| {
+                if rng.gen_bool(0.3) {
+                    character
+                } else {
+                    random_letter(rng)
+                }
+            })
+            .collect::<String>();
+
+        writeln!(output, "{}-{} {}: {}", first, second, character, password)
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_input;
+
+    #[test]
+    fn parses_password_line() {
+        let password_line = parse_password_line("1-3 a: abcde").unwrap();
+
+        assert_eq!(password_line.first, 1);
+        assert_eq!(password_line.second, 3);
+        assert_eq!(password_line.character, 'a');
+        assert_eq!(password_line.password, "abcde");
+    }
+
+    #[test]
+    fn rejects_invalid_password_line() {
+        assert!(parse_password_line("1-3 a abcde").is_err());
+        assert!(parse_password_line("1 a: abcde").is_err());
+    }
+
+    #[test]
+    fn solves_sample() {
+        assert_eq!(
+            parse_input(&mut sample_input(2, "sample")).unwrap(),
+            ValidPasswords {
+                required_count: 2,
+                required_positions: 1
+            }
+        );
+    }
 }
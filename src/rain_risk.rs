@@ -1,223 +1,277 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_isize, parse_lines, Command};
+use crate::geometry::{Heading, Point, Vector};
+use crate::{
+    default_sub_command, parse_isize, preset_arg, value_or_preset, GenerateOptions, InputReader,
+    ParseError, Puzzle, PRESET,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
-use nom::{character::complete, combinator::map_res, sequence::tuple};
-use simple_error::SimpleError;
-use std::collections::HashMap;
-use std::convert::TryFrom;
+use clap::{App, Arg, ArgMatches};
+use nom::{
+    character::complete,
+    combinator::{all_consuming, map_opt},
+    sequence::tuple,
+};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const RAIN_RISK: Command = Command::new(sub_command, "rain-risk", run);
+pub struct RainRisk;
 
-#[derive(Debug)]
-struct RainRiskArgs {
-    file: String,
-    direction_strategy: DirectionStrategy,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RainRiskArgs {
+    pub file: String,
+    pub direction_strategy: DirectionStrategy,
 }
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+#[derive(Debug, Clone, EnumString, EnumVariantNames, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab_case")]
-enum DirectionStrategy {
+#[serde(rename_all = "kebab-case")]
+pub enum DirectionStrategy {
     Relative,
     Waypoint,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy, Eq, Hash)]
-enum Direction {
-    North(isize),
-    East(isize),
-    South(isize),
-    West(isize),
-    Left(usize),
-    Right(usize),
+pub enum Direction {
+    Move(Heading, isize),
+    Left(isize),
+    Right(isize),
     Forward(isize),
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &RAIN_RISK,
-        "Takes a file with directions and returns the manhatten distance the ship moved.",
-        "Path to the input file. Each line contains one direction instruction",
-    )
-    .arg(
-        Arg::with_name("direction-strategy")
-            .short("d")
-            .help(
-                "How to interperate the direction instructions. The possible value are as follows:\n\n\
-            relative: Directions are relative to the ship.\n\n\
-            waypoint: Directions are relayove to a waypoint.\n",
-            )
-            .takes_value(true)
-            .possible_values(&DirectionStrategy::VARIANTS)
-            .required(true),
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about(
-                "Finds the Manhattan distance using relative direction-strategy and the default input.",
-            )
-            .version("1.0.0"),
-    )
-    .subcommand(
-        SubCommand::with_name("part2")
-            .about(
-                "Finds the Manhattan distance using waypoint direction-strategy and the default input.",
-            )
-            .version("1.0.0"),
-    )
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Move(Heading::North, value) => write!(f, "N{}", value),
+            Direction::Move(Heading::East, value) => write!(f, "E{}", value),
+            Direction::Move(Heading::South, value) => write!(f, "S{}", value),
+            Direction::Move(Heading::West, value) => write!(f, "W{}", value),
+            Direction::Left(value) => write!(f, "L{}", value),
+            Direction::Right(value) => write!(f, "R{}", value),
+            Direction::Forward(value) => write!(f, "F{}", value),
+        }
+    }
 }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let rain_risk_arguments = match arguments.subcommand_name() {
-        Some("part1") => RainRiskArgs {
-            file: "day12/input.txt".to_string(),
-            direction_strategy: DirectionStrategy::Relative,
-        },
-        Some("part2") => RainRiskArgs {
-            file: "day12/input.txt".to_string(),
-            direction_strategy: DirectionStrategy::Waypoint,
-        },
-        _ => RainRiskArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            direction_strategy: value_t_or_exit!(
-                arguments.value_of("direction-strategy"),
-                DirectionStrategy
-            ),
-        },
-    };
+impl Puzzle for RainRisk {
+    type Args = RainRiskArgs;
+    type Input = Vec<Direction>;
+    type Answer = isize;
 
-    process_directions(&rain_risk_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
-        })
-        .map(|_| ())
-}
+    const NAME: &'static str = "rain-risk";
+    const DAY: usize = 12;
+    const GENERATE_ABOUT: &'static str =
+        "Writes size navigation instructions. Moves go up to 100 and turns are right angles.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Takes a file with directions and returns the manhatten distance the ship moved.",
+            "Path to the input file. Each line contains one direction instruction",
+        )
+        .arg(
+            Arg::with_name("direction-strategy")
+                .short("d")
+                .help(
+                    "How to interperate the direction instructions. The possible value are as follows:\n\n\
+                relative: Directions are relative to the ship.\n\n\
+                waypoint: Directions are relayove to a waypoint.\n",
+                )
+                .takes_value(true)
+                .possible_values(DirectionStrategy::VARIANTS)
+                .required_unless(PRESET),
+        )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds the Manhattan distance using relative direction-strategy and the \
+            default input.\n\n\
+            part2: Finds the Manhattan distance using waypoint direction-strategy and the \
+            default input.\n",
+        ))
+    }
 
-fn process_directions(rain_risk_arguments: &RainRiskArgs) -> Result<isize, Error> {
-    file_to_lines(&rain_risk_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_directions))
-        .map(|directions| {
-            let (x, y) = match rain_risk_arguments.direction_strategy {
-                DirectionStrategy::Relative => travel_directions(&directions),
-                DirectionStrategy::Waypoint => travel_directions_waypoint(&directions),
-            };
-            x.abs() + y.abs()
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&RainRiskArgs>,
+    ) -> Result<RainRiskArgs, Error> {
+        Ok(RainRiskArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            direction_strategy: value_or_preset(
+                arguments,
+                "direction-strategy",
+                preset.map(|preset| &preset.direction_strategy),
+            )?,
         })
-}
+    }
 
-fn travel_directions(directions: &Vec<Direction>) -> (isize, isize) {
-    let mut point = (0, 0);
-    let mut current_direction = Direction::East(0);
-    let (index_direction, direction_index) = generate_index_directions();
-
-    for direction in directions {
-        match direction {
-            Direction::North(x) => point.0 += x,
-            Direction::East(x) => point.1 += x,
-            Direction::South(x) => point.0 -= x,
-            Direction::West(x) => point.1 -= x,
-            Direction::Right(x) => {
-                current_direction = *index_direction
-                    .get(&((direction_index.get(&current_direction).unwrap() + (x / 90)) % 4))
-                    .unwrap()
-            }
-            Direction::Left(x) => {
-                current_direction = *index_direction
-                    .get(&((direction_index.get(&current_direction).unwrap() + 4 - (x / 90)) % 4))
-                    .unwrap()
-            }
-            Direction::Forward(x) => match current_direction {
-                Direction::North(_) => point.0 += x,
-                Direction::East(_) => point.1 += x,
-                Direction::South(_) => point.0 -= x,
-                Direction::West(_) => point.1 -= x,
-                _ => (),
-            },
+    fn part1_args(&self, file: String) -> RainRiskArgs {
+        RainRiskArgs {
+            file,
+            direction_strategy: DirectionStrategy::Relative,
         }
     }
 
-    point
-}
+    fn part2_args(&self, file: String) -> Option<RainRiskArgs> {
+        Some(RainRiskArgs {
+            file,
+            direction_strategy: DirectionStrategy::Waypoint,
+        })
+    }
 
-fn travel_directions_waypoint(directions: &Vec<Direction>) -> (isize, isize) {
-    let mut point = (0, 0);
-    let mut waypoint = (1, 10);
-
-    for direction in directions {
-        match direction {
-            Direction::North(x) => waypoint.0 += x,
-            Direction::East(x) => waypoint.1 += x,
-            Direction::South(x) => waypoint.0 -= x,
-            Direction::West(x) => waypoint.1 -= x,
-            Direction::Right(x) => {
-                let steps = x / 90;
-                for _ in 0..steps {
-                    waypoint = rotate_waypoint_right_once(&waypoint);
-                }
-            }
-            Direction::Left(x) => {
-                let steps = x / 90;
-                for _ in 0..steps {
-                    waypoint = rotate_waypoint_left_once(&waypoint);
-                }
-            }
-            Direction::Forward(x) => {
-                point.0 += waypoint.0 * x;
-                point.1 += waypoint.1 * x
-            }
-        }
+    fn file<'a>(&self, arguments: &'a RainRiskArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: RainRiskArgs, file: String) -> RainRiskArgs {
+        RainRiskArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &RainRiskArgs,
+    ) -> Result<Vec<Direction>, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
     }
 
-    point
+    fn solve(&self, directions: Vec<Direction>, arguments: &RainRiskArgs) -> Result<isize, Error> {
+        let destination = match arguments.direction_strategy {
+            DirectionStrategy::Relative => travel_directions(&directions)?,
+            DirectionStrategy::Waypoint => travel_directions_waypoint(&directions),
+        };
+        Ok(destination.manhattan_distance(&Point::ORIGIN))
+    }
 }
 
-fn rotate_waypoint_right_once(waypoint: &(isize, isize)) -> (isize, isize) {
-    (-waypoint.1, waypoint.0)
+pub fn travel_directions(directions: &[Direction]) -> Result<Point, Error> {
+    directions
+        .iter()
+        .try_fold(
+            (Point::ORIGIN, Heading::East),
+            |(point, heading), direction| match direction {
+                Direction::Move(towards, distance) => {
+                    Ok((point + towards.vector() * *distance, heading))
+                }
+                Direction::Left(degrees) => {
+                    heading.rotate(*degrees).map(|heading| (point, heading))
+                }
+                Direction::Right(degrees) => {
+                    heading.rotate(-degrees).map(|heading| (point, heading))
+                }
+                Direction::Forward(distance) => Ok((point + heading.vector() * *distance, heading)),
+            },
+        )
+        .map(|(point, _)| point)
 }
 
-fn rotate_waypoint_left_once(waypoint: &(isize, isize)) -> (isize, isize) {
-    (waypoint.1, -waypoint.0)
+pub fn travel_directions_waypoint(directions: &[Direction]) -> Point {
+    directions
+        .iter()
+        .fold(
+            (Point::ORIGIN, Vector::new(10, 1)),
+            |(point, waypoint), direction| match direction {
+                Direction::Move(towards, distance) => {
+                    (point, waypoint + towards.vector() * *distance)
+                }
+                Direction::Left(degrees) => (point, waypoint.rotate(*degrees)),
+                Direction::Right(degrees) => (point, waypoint.rotate(-degrees)),
+                Direction::Forward(times) => (point + waypoint * *times, waypoint),
+            },
+        )
+        .0
 }
 
-fn generate_index_directions() -> (HashMap<usize, Direction>, HashMap<Direction, usize>) {
-    let index_direction: HashMap<usize, Direction> = [
-        Direction::North(0),
-        Direction::East(0),
-        Direction::South(0),
-        Direction::West(0),
-    ]
-    .iter()
-    .enumerate()
-    .map(|(index, direction)| (index, *direction))
-    .collect();
-    let direction_index: HashMap<Direction, usize> = index_direction
-        .clone()
-        .into_iter()
-        .map(|(index, direction)| (direction, index))
-        .collect();
-
-    (index_direction, direction_index)
+pub fn parse_input(input: &mut InputReader) -> Result<Vec<Direction>, Error> {
+    input.parse_lines(parse_directions)
 }
 
-fn parse_directions(line: &String) -> Result<Direction, Error> {
-    map_res(
+fn parse_directions(line: &str) -> Result<Direction, Error> {
+    all_consuming(map_opt(
         tuple((complete::alpha1, parse_isize)),
         |(direction, value)| match direction {
-            "N" => Ok(Direction::North(value)),
-            "E" => Ok(Direction::East(value)),
-            "S" => Ok(Direction::South(value)),
-            "W" => Ok(Direction::West(value)),
-            "R" => usize::try_from(value)
-                .map(|value| Direction::Right(value))
-                .map_err(|_| SimpleError::new("Could not convert to isize")),
-            "L" => usize::try_from(value)
-                .map(|value| Direction::Left(value))
-                .map_err(|_| SimpleError::new("Could not convert to isize")),
-            "F" => Ok(Direction::Forward(value)),
-            x => Err(SimpleError::new(format!("Unkonwn direction {:#?}", x))),
+            "N" => Some(Direction::Move(Heading::North, value)),
+            "E" => Some(Direction::Move(Heading::East, value)),
+            "S" => Some(Direction::Move(Heading::South, value)),
+            "W" => Some(Direction::Move(Heading::West, value)),
+            "L" | "R" if value % 90 != 0 => None,
+            "L" => Some(Direction::Left(value)),
+            "R" => Some(Direction::Right(value)),
+            "F" => Some(Direction::Forward(value)),
+            _ => None,
         },
-    )(line)
+    ))(line)
     .map(|(_, direction)| direction)
-    .map_err(|_| SimpleError::new("Parse failure").into())
+    .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+        ParseError::from_nom(
+            line,
+            err,
+            "one of N, E, S, W or F followed by an integer, or L or R followed by a multiple of 90",
+        )
+        .into()
+    })
+}
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    (0..options.size)
+        .map(|_| {
+            let distance = rng.gen_range(1..=100);
+            let degrees = 90 * rng.gen_range(1..=3);
+            match rng.gen_range(0..8) {
+                0 => Direction::Move(Heading::North, distance),
+                1 => Direction::Move(Heading::East, distance),
+                2 => Direction::Move(Heading::South, distance),
+                3 => Direction::Move(Heading::West, distance),
+                4 => Direction::Left(degrees),
+                5 => Direction::Right(degrees),
+                _ => Direction::Forward(distance),
+            }
+        })
+        .try_for_each(|direction| writeln!(output, "{}", direction))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_input;
+
+    #[test]
+    fn parses_directions() {
+        assert_eq!(
+            parse_directions("N3").unwrap(),
+            Direction::Move(Heading::North, 3)
+        );
+        assert_eq!(parse_directions("R270").unwrap(), Direction::Right(270));
+        assert_eq!(parse_directions("F10").unwrap(), Direction::Forward(10));
+        assert!(parse_directions("R45").is_err());
+        assert!(parse_directions("X10").is_err());
+    }
+
+    #[test]
+    fn solves_sample() {
+        let directions = parse_input(&mut sample_input(12, "sample")).unwrap();
+
+        assert_eq!(travel_directions(&directions).unwrap(), Point::new(17, -8));
+        assert!(travel_directions(&[Direction::Left(45)]).is_err());
+        assert_eq!(
+            travel_directions_waypoint(&directions),
+            Point::new(214, -72)
+        );
+    }
 }
@@ -0,0 +1,223 @@
+use crate::{catch_panic, parse_puzzle_input, Answer, Puzzle};
+use anyhow::Error;
+use serde_json::Value;
+use simple_error::SimpleError;
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+const HELP: &str = "Commands:
+  solve                Solves the loaded input with the current arguments.
+  show                 Prints the current arguments, and the input if the puzzle can draw it.
+  step                 Advances the loaded input by one round, if the puzzle supports it.
+  reload               Parses the input file again, undoing any steps.
+  <argument> <value>   Changes an argument, e.g. \"tolerance 6\". Changing the file loads it.
+  help                 Prints this message.
+  quit                 Leaves the repl.";
+
+struct Session<'a, P: Puzzle> {
+    puzzle: &'a P,
+    arguments: P::Args,
+    parsed_with: P::Args,
+    input: P::Input,
+    lenient: bool,
+}
+
+/// Parses the input once, then reads commands until quit or the end of the commands.
+pub fn run<P: Puzzle>(
+    puzzle: &P,
+    arguments: P::Args,
+    lenient: bool,
+    commands: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    let mut session = Session {
+        puzzle,
+        input: parse_puzzle_input(puzzle, &arguments, lenient)?,
+        parsed_with: arguments.clone(),
+        arguments,
+        lenient,
+    };
+    writeln!(
+        output,
+        "Loaded {}, type help for a list of commands.",
+        puzzle.file(&session.arguments)
+    )?;
+
+    loop {
+        write!(output, "{}> ", P::NAME)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if commands.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            return Ok(());
+        }
+
+        match line.trim() {
+            "" => (),
+            "quit" | "exit" => return Ok(()),
+            command => {
+                // A panicking solve shouldn't lose the loaded input, so it is reported like any error
+                if let Err(err) = catch_panic(|| session.execute(command, output)) {
+                    writeln!(output, "error: {}", err)?;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, P: Puzzle> Session<'a, P> {
+    fn execute(&mut self, command: &str, output: &mut dyn Write) -> Result<(), Error> {
+        let (name, value) = command
+            .split_once(char::is_whitespace)
+            .map(|(name, value)| (name, value.trim()))
+            .unwrap_or((command, ""));
+
+        match (name, value) {
+            ("help", "") => writeln!(output, "{}", HELP)?,
+            ("solve", "") => {
+                let start = Instant::now();
+                let answer: Answer = self
+                    .puzzle
+                    .solve(self.input.clone(), &self.arguments)?
+                    .into();
+                writeln!(
+                    output,
+                    "{} ({:.3}ms)",
+                    answer,
+                    start.elapsed().as_secs_f64() * 1000.0
+                )?;
+            }
+            ("show", "") => {
+                writeln!(output, "{}", serde_json::to_string_pretty(&self.arguments)?)?;
+                self.draw(output)?;
+            }
+            ("step", "") => {
+                self.input = self.puzzle.step(self.input.clone(), &self.arguments)?;
+                self.draw(output)?;
+            }
+            ("reload", "") => {
+                self.input = parse_puzzle_input(self.puzzle, &self.arguments, self.lenient)?;
+                self.parsed_with = self.arguments.clone();
+            }
+            (name, "") => {
+                return Err(SimpleError::new(format!(
+                    "Unknown command {}, type help for a list of commands",
+                    name
+                ))
+                .into())
+            }
+            (name, value) => self.set(name, value)?,
+        }
+
+        Ok(())
+    }
+
+    fn draw(&self, output: &mut dyn Write) -> Result<(), Error> {
+        if let Some(picture) = self.puzzle.display_input(&self.input) {
+            write!(output, "{}", picture)?;
+        }
+        Ok(())
+    }
+
+    /// Sets one field of the arguments by its serialized name. Strings take the value as is,
+    /// anything else is read as JSON, e.g. numbers, true or false.
+    fn set(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let mut arguments = serde_json::to_value(&self.arguments)?;
+        let fields = arguments
+            .as_object_mut()
+            .ok_or_else(|| SimpleError::new("Arguments are not a set of fields"))?;
+        let names = fields.keys().cloned().collect::<Vec<String>>();
+        let field = fields.get_mut(&name.replace('-', "_")).ok_or_else(|| {
+            SimpleError::new(format!(
+                "Unknown argument {}, arguments are: {}",
+                name,
+                names.join(", ")
+            ))
+        })?;
+        *field = match field {
+            Value::String(_) => Value::String(value.to_string()),
+            _ => serde_json::from_str(value)?,
+        };
+
+        let arguments = serde_json::from_value::<P::Args>(arguments)?;
+        if self.puzzle.file(&arguments) != self.puzzle.file(&self.parsed_with)
+            || !self.puzzle.reuses_input(&self.parsed_with, &arguments)
+        {
+            self.input = parse_puzzle_input(self.puzzle, &arguments, self.lenient)?;
+            self.parsed_with = arguments.clone();
+        }
+        self.arguments = arguments;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handy_haversacks::HandyHaversacks;
+    use crate::seating_system::SeatingSystem;
+
+    fn session(commands: &str) -> String {
+        let mut output = Vec::new();
+        run(
+            &SeatingSystem,
+            SeatingSystem.part1_args(format!("{}/day11/sample.txt", env!("CARGO_MANIFEST_DIR"))),
+            false,
+            &mut commands.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn solves_again_after_changing_arguments() {
+        let output = session("solve\nadjacency-definition line-of-sight\ntolerance 5\nsolve\n");
+
+        assert!(output.contains("seating-system> 37 ("));
+        assert!(output.contains("seating-system> 26 ("));
+    }
+
+    #[test]
+    fn steps_the_loaded_input() {
+        let output = session("step\nstep\nreload\nsolve\nquit\n");
+
+        assert!(output.contains("#.##.##.##\n"));
+        assert!(output.contains("#.LL.L#.##\n"));
+        assert!(output.contains("seating-system> 37 ("));
+    }
+
+    #[test]
+    fn reports_bad_commands_and_keeps_going() {
+        let output =
+            session("bogus\ntolerance many\nadjacency-definition sideways\nspeed 3\nsolve\n");
+
+        assert!(output.contains("error: Unknown command bogus"));
+        assert!(output.contains("error: expected value"));
+        assert!(output.contains("error: unknown variant `sideways`"));
+        assert!(output.contains(
+            "error: Unknown argument speed, arguments are: adjacency_definition, file, tolerance"
+        ));
+        assert!(output.contains("seating-system> 37 ("));
+    }
+
+    #[test]
+    fn keeps_going_after_a_failed_solve() {
+        let mut output = Vec::new();
+        run(
+            &HandyHaversacks,
+            HandyHaversacks
+                .part2_args(format!("{}/day7/sample.txt", env!("CARGO_MANIFEST_DIR")))
+                .unwrap(),
+            false,
+            &mut "sack-name dark redd\nsolve\nsack-name dark olive\nsolve\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("error: No rule for bag dark redd"));
+        assert!(output.contains("handy-haversacks> 7 ("));
+    }
+}
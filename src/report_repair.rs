@@ -1,88 +1,182 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_lines, Command, SumChecker};
+use crate::{
+    default_sub_command, preset_arg, value_or_preset, GenerateOptions, InputReader, ParseError,
+    Puzzle, SumChecker, PRESET,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches};
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
 
-pub const REPORT_REPAIR: Command = Command::new(sub_command, "report-repair", run);
+pub struct ReportRepair;
 
-struct ReportRepairArgs {
-    file: String,
-    target: isize,
-    number: usize,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportRepairArgs {
+    pub file: String,
+    pub target: isize,
+    pub number: usize,
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &REPORT_REPAIR,
-        "Looks through the input for n numbers that sum to target. \
-    Then multiplies the result and produces the output.",
-        "Path to the input file. Input should be newline delimited integers.",
-    )
-    .arg(
-        Arg::with_name("target")
-            .short("t")
-            .help("Target sum to find.")
-            .takes_value(true)
-            .required(true),
-    )
-    .arg(
-        Arg::with_name("number")
-            .short("n")
-            .help("Number of items that must be used in the sum")
-            .takes_value(true)
-            .required(true),
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about(
-                "Searches the default input for two values that sum to 2020. \
-Then multiplies the result and produces the output.",
-            )
-            .version("1.0.0"),
-    )
-    .subcommand(
-        SubCommand::with_name("part2")
-            .about(
-                "Searches the default input for three values that sum to 2020. \
-Then multiplies the result and produces the output.",
-            )
-            .version("1.0.0"),
-    )
-}
+impl Puzzle for ReportRepair {
+    type Args = ReportRepairArgs;
+    type Input = Vec<isize>;
+    type Answer = isize;
+
+    const NAME: &'static str = "report-repair";
+    const DAY: usize = 1;
+    const GENERATE_ABOUT: &'static str =
+        "Writes size integers between 1 and 2019 which include a pair and a triple summing to 2020.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Looks through the input for n numbers that sum to target. \
+        Then multiplies the result and produces the output.",
+            "Path to the input file. Input should be newline delimited integers.",
+        )
+        .arg(
+            Arg::with_name("target")
+                .short("t")
+                .help("Target sum to find.")
+                .takes_value(true)
+                .required_unless(PRESET),
+        )
+        .arg(
+            Arg::with_name("number")
+                .short("n")
+                .help("Number of items that must be used in the sum")
+                .takes_value(true)
+                .required_unless(PRESET),
+        )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Searches the default input for two values that sum to 2020. Then \
+            multiplies the result and produces the output.\n\n\
+            part2: Searches the default input for three values that sum to 2020. Then \
+            multiplies the result and produces the output.\n",
+        ))
+    }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let report_arguments = match arguments.subcommand_name() {
-        Some("part1") => ReportRepairArgs {
-            file: "day1/input.txt".to_string(),
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&ReportRepairArgs>,
+    ) -> Result<ReportRepairArgs, Error> {
+        Ok(ReportRepairArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            target: value_or_preset(arguments, "target", preset.map(|preset| &preset.target))?,
+            number: value_or_preset(arguments, "number", preset.map(|preset| &preset.number))?,
+        })
+    }
+
+    fn part1_args(&self, file: String) -> ReportRepairArgs {
+        ReportRepairArgs {
+            file,
             target: 2020,
             number: 2,
-        },
-        Some("part2") => ReportRepairArgs {
-            file: "day1/input.txt".to_string(),
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<ReportRepairArgs> {
+        Some(ReportRepairArgs {
+            file,
             target: 2020,
             number: 3,
-        },
-        _ => ReportRepairArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            target: value_t_or_exit!(arguments.value_of("target"), isize),
-            number: value_t_or_exit!(arguments.value_of("number"), usize),
-        },
-    };
-
-    file_to_lines(&report_arguments.file)
-        .and_then(|lines| {
-            parse_lines(lines, |line| line.parse::<isize>()).map_err(|err| err.into())
         })
-        .and_then(|lines| {
-            find_muliple_of_sum_of_n(&report_arguments.target, &lines, report_arguments.number)
-        })
-        .map(|result| {
-            println!("{:#?}", result);
-        })
-        .map(|_| ())
+    }
+
+    fn file<'a>(&self, arguments: &'a ReportRepairArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: ReportRepairArgs, file: String) -> ReportRepairArgs {
+        ReportRepairArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &ReportRepairArgs,
+    ) -> Result<Vec<isize>, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(&self, numbers: Vec<isize>, arguments: &ReportRepairArgs) -> Result<isize, Error> {
+        find_muliple_of_sum_of_n(&arguments.target, &numbers, arguments.number)
+    }
+}
+
+pub fn parse_input(input: &mut InputReader) -> Result<Vec<isize>, Error> {
+    input.parse_lines(|line| {
+        line.parse::<isize>()
+            .map_err(|_| ParseError::new(line, 0, "an integer").into())
+    })
 }
 
-fn find_muliple_of_sum_of_n(target: &isize, input: &Vec<isize>, n: usize) -> Result<isize, Error> {
+pub fn find_muliple_of_sum_of_n(target: &isize, input: &[isize], n: usize) -> Result<isize, Error> {
     SumChecker::with_vec(input)
         .find_sum_of_n(target, n)
-        .map(|result| result.into_iter().fold(1, |acc, number| acc * number))
+        .map(|result| result.into_iter().product::<isize>())
+}
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    options.require_size(5, usize::MAX)?;
+    let pair = rng.gen_range(1..2020);
+    let first = rng.gen_range(1..2019);
+    let second = rng.gen_range(1..2020 - first);
+
+    let mut numbers = vec![pair, 2020 - pair, first, second, 2020 - first - second];
+    numbers.extend((numbers.len()..options.size).map(|_| rng.gen_range(1..2020)));
+    numbers.shuffle(rng);
+    numbers
+        .iter()
+        .try_for_each(|number| writeln!(output, "{}", number))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_input;
+
+    #[test]
+    fn parses_one_integer_per_line() {
+        assert_eq!(
+            parse_input(&mut InputReader::from_text("1721\n-979\n")).unwrap(),
+            vec![1721, -979]
+        );
+        assert!(parse_input(&mut InputReader::from_text("1721\nabc\n")).is_err());
+    }
+
+    #[test]
+    fn solves_sample() {
+        let numbers = parse_input(&mut sample_input(1, "sample")).unwrap();
+
+        assert_eq!(
+            find_muliple_of_sum_of_n(&2020, &numbers, 2).unwrap(),
+            514579
+        );
+        assert_eq!(
+            find_muliple_of_sum_of_n(&2020, &numbers, 3).unwrap(),
+            241861950
+        );
+    }
+
+    #[test]
+    fn fails_when_nothing_sums_to_target() {
+        assert!(find_muliple_of_sum_of_n(&2020, &[1, 2, 3], 2).is_err());
+    }
 }
@@ -1,139 +1,207 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_lines, Command};
+use crate::grid::{Grid, Neighbourhood, Position, Wrap};
+use crate::{
+    default_sub_command, preset_arg, value_or_preset, GenerateOptions, InputReader, Puzzle, PRESET,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
-use nom::{branch::alt, character::complete, combinator::map, multi::many1};
-use simple_error::SimpleError;
+use clap::{App, Arg, ArgMatches};
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
+use std::iter;
 use strum::VariantNames;
 use strum_macros::{EnumString, EnumVariantNames};
 
-pub const SEATING_SYSTEM: Command = Command::new(sub_command, "seating-system", run);
+pub struct SeatingSystem;
 
-#[derive(Debug)]
-struct SeatingSystemArgs {
-    file: String,
-    tolerance: usize,
-    adjacency_definition: AdjacencyDefinition,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeatingSystemArgs {
+    pub file: String,
+    pub tolerance: usize,
+    pub adjacency_definition: AdjacencyDefinition,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
-enum FloorTile {
+pub enum FloorTile {
     Floor,
     Seat { occupied: bool },
 }
 
-#[derive(Debug, EnumString, EnumVariantNames)]
+impl fmt::Display for FloorTile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FloorTile::Floor => '.',
+                FloorTile::Seat { occupied: true } => '#',
+                FloorTile::Seat { occupied: false } => 'L',
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, EnumString, EnumVariantNames, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab_case")]
-enum AdjacencyDefinition {
+#[serde(rename_all = "kebab-case")]
+pub enum AdjacencyDefinition {
     DirectlyNextTo,
     LineOfSight,
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &SEATING_SYSTEM,
-        "Takes a file with a seating chart and finds stats about people sitting",
-        "Path to the input file. Each line contains one row of seats.",
-    )
-    .arg(
-        Arg::with_name("tolerance")
-            .short("t")
-            .help("The amount of adjacent seats people are willing to sit beside before leaving")
-            .takes_value(true)
-            .required(true),
-    )
-    .arg(
-        Arg::with_name("adjacency")
-            .short("a")
-            .help(
-                "The definition of adjaceny. The possible definitions are as follows:\n\n\
-            directly-next-to: Tiles directly next to the target tile are adjacent.\n\n\
-            line-of-sight: The first Seat tile in a direct is adjacent.\n",
-            )
-            .takes_value(true)
-            .possible_values(&AdjacencyDefinition::VARIANTS)
-            .required(true),
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about(
-                "Finds an equalibrium for steating arrangements with a tolerance of 4, and \
-                adjacency of directly-next-to and then returns the number of occupied seats \
-                with the default input.",
-            )
-            .version("1.0.0"),
-    )
-    .subcommand(
-        SubCommand::with_name("part2")
-            .about(
-                "Finds an equalibrium for steating arrangements with a tolerance of 5, and \
-                adjacency of line-of-sight and then returns the number of occupied seats \
-                with the default input.",
-            )
-            .version("1.0.0"),
-    )
-}
+impl Puzzle for SeatingSystem {
+    type Args = SeatingSystemArgs;
+    type Input = Grid<FloorTile>;
+    type Answer = usize;
+
+    const NAME: &'static str = "seating-system";
+    const DAY: usize = 11;
+    const GENERATE_ABOUT: &'static str =
+        "Writes a seating chart size rows tall and width columns wide of empty seats with some floor. \
+        Seats that would flip forever are replaced with floor, which gets slow past a few hundred rows.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Takes a file with a seating chart and finds stats about people sitting",
+            "Path to the input file. Each line contains one row of seats.",
+        )
+        .arg(
+            Arg::with_name("tolerance")
+                .short("t")
+                .help(
+                    "The amount of adjacent seats people are willing to sit beside before leaving",
+                )
+                .takes_value(true)
+                .required_unless(PRESET),
+        )
+        .arg(
+            Arg::with_name("adjacency")
+                .short("a")
+                .help(
+                    "The definition of adjaceny. The possible definitions are as follows:\n\n\
+                directly-next-to: Tiles directly next to the target tile are adjacent.\n\n\
+                line-of-sight: The first Seat tile in a direct is adjacent.\n",
+                )
+                .takes_value(true)
+                .possible_values(AdjacencyDefinition::VARIANTS)
+                .required_unless(PRESET),
+        )
+        .arg(preset_arg(&["part1", "part2"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds an equalibrium for steating arrangements with a tolerance of 4, and \
+            adjacency of directly-next-to and then returns the number of occupied seats with \
+            the default input.\n\n\
+            part2: Finds an equalibrium for steating arrangements with a tolerance of 5, and \
+            adjacency of line-of-sight and then returns the number of occupied seats with \
+            the default input.\n",
+        ))
+    }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let seating_system_arguments = match arguments.subcommand_name() {
-        Some("part1") => SeatingSystemArgs {
-            file: "day11/input.txt".to_string(),
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&SeatingSystemArgs>,
+    ) -> Result<SeatingSystemArgs, Error> {
+        Ok(SeatingSystemArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            tolerance: value_or_preset(
+                arguments,
+                "tolerance",
+                preset.map(|preset| &preset.tolerance),
+            )?,
+            adjacency_definition: value_or_preset(
+                arguments,
+                "adjacency",
+                preset.map(|preset| &preset.adjacency_definition),
+            )?,
+        })
+    }
+
+    fn part1_args(&self, file: String) -> SeatingSystemArgs {
+        SeatingSystemArgs {
+            file,
             tolerance: 4,
             adjacency_definition: AdjacencyDefinition::DirectlyNextTo,
-        },
-        Some("part2") => SeatingSystemArgs {
-            file: "day11/input.txt".to_string(),
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<SeatingSystemArgs> {
+        Some(SeatingSystemArgs {
+            file,
             tolerance: 5,
             adjacency_definition: AdjacencyDefinition::LineOfSight,
-        },
-        _ => SeatingSystemArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            tolerance: value_t_or_exit!(arguments.value_of("tolerance"), usize),
-            adjacency_definition: value_t_or_exit!(
-                arguments.value_of("adjacency"),
-                AdjacencyDefinition
-            ),
-        },
-    };
-
-    process_seat_layout(&seating_system_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
         })
-        .map(|_| ())
-}
+    }
 
-fn process_seat_layout(seating_system_arguments: &SeatingSystemArgs) -> Result<usize, Error> {
-    file_to_lines(&seating_system_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_row_of_seats))
-        .map(|seating_arrangement| {
-            find_equalibrium(
-                &seating_arrangement,
-                &seating_system_arguments.tolerance,
-                &seating_system_arguments.adjacency_definition,
-            )
-            .into_iter()
-            .fold(0usize, |acc, row| {
-                acc + row
-                    .into_iter()
-                    .filter(|tile| match tile {
-                        FloorTile::Seat { occupied: true } => true,
-                        _ => false,
-                    })
-                    .count()
-            })
-        })
+    fn file<'a>(&self, arguments: &'a SeatingSystemArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, arguments: SeatingSystemArgs, file: String) -> SeatingSystemArgs {
+        SeatingSystemArgs { file, ..arguments }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &SeatingSystemArgs,
+    ) -> Result<Grid<FloorTile>, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn display_input(&self, seating_arrangement: &Grid<FloorTile>) -> Option<String> {
+        Some(seating_arrangement.to_string())
+    }
+
+    fn step(
+        &self,
+        seating_arrangement: Grid<FloorTile>,
+        arguments: &SeatingSystemArgs,
+    ) -> Result<Grid<FloorTile>, Error> {
+        Ok(iterate_seats(
+            &seating_arrangement,
+            &arguments.tolerance,
+            &arguments.adjacency_definition,
+        ))
+    }
+
+    fn solve(
+        &self,
+        seating_arrangement: Grid<FloorTile>,
+        arguments: &SeatingSystemArgs,
+    ) -> Result<usize, Error> {
+        Ok(find_equalibrium(
+            &seating_arrangement,
+            &arguments.tolerance,
+            &arguments.adjacency_definition,
+        )
+        .values()
+        .filter(|tile| matches!(tile, FloorTile::Seat { occupied: true }))
+        .count())
+    }
 }
 
-fn find_equalibrium(
-    seating_arrangement: &Vec<Vec<FloorTile>>,
+pub fn find_equalibrium(
+    seating_arrangement: &Grid<FloorTile>,
     tolerance: &usize,
     adjacency_definition: &AdjacencyDefinition,
-) -> Vec<Vec<FloorTile>> {
-    let mut previous_arrangement = seating_arrangement.to_vec();
+) -> Grid<FloorTile> {
+    let mut previous_arrangement = seating_arrangement.clone();
     loop {
         let next_arrangement =
             iterate_seats(&previous_arrangement, tolerance, adjacency_definition);
-        if next_arrangement == *previous_arrangement {
+        if next_arrangement == previous_arrangement {
             break;
         }
 
@@ -143,266 +211,242 @@ fn find_equalibrium(
     previous_arrangement
 }
 
-fn iterate_seats(
-    seating_arrangement: &Vec<Vec<FloorTile>>,
+pub fn iterate_seats(
+    seating_arrangement: &Grid<FloorTile>,
     tolerance: &usize,
     adjacency_definition: &AdjacencyDefinition,
-) -> Vec<Vec<FloorTile>> {
-    seating_arrangement
-        .into_iter()
-        .enumerate()
-        .map(|(y, row)| {
-            row.into_iter()
-                .enumerate()
-                .map(|(x, tile)| match tile {
-                    FloorTile::Seat { occupied: true } => {
-                        let adjacent_tiles = match adjacency_definition {
-                            AdjacencyDefinition::DirectlyNextTo => {
-                                get_adjacent_tiles(&x, &y, seating_arrangement)
-                            }
-                            AdjacencyDefinition::LineOfSight => {
-                                get_line_of_sight_seats(&x, &y, seating_arrangement)
-                            }
-                        };
-                        match count_occupided_seats(adjacent_tiles) {
-                            _x if _x >= *tolerance => FloorTile::Seat { occupied: false },
-                            _ => FloorTile::Seat { occupied: true },
-                        }
-                    }
-                    FloorTile::Seat { occupied: false } => {
-                        let adjacent_tiles = match adjacency_definition {
-                            AdjacencyDefinition::DirectlyNextTo => {
-                                get_adjacent_tiles(&x, &y, seating_arrangement)
-                            }
-                            AdjacencyDefinition::LineOfSight => {
-                                get_line_of_sight_seats(&x, &y, seating_arrangement)
-                            }
-                        };
-                        match count_occupided_seats(adjacent_tiles) {
-                            0 => FloorTile::Seat { occupied: true },
-                            _ => FloorTile::Seat { occupied: false },
-                        }
-                    }
-                    FloorTile::Floor => FloorTile::Floor,
-                })
-                .collect()
-        })
-        .collect()
+) -> Grid<FloorTile> {
+    seating_arrangement.map(|position, tile| match tile {
+        FloorTile::Seat { occupied: true } => {
+            match count_occupided_seats(get_adjacent_seats(
+                position,
+                seating_arrangement,
+                adjacency_definition,
+            )) {
+                _x if _x >= *tolerance => FloorTile::Seat { occupied: false },
+                _ => FloorTile::Seat { occupied: true },
+            }
+        }
+        FloorTile::Seat { occupied: false } => {
+            match count_occupided_seats(get_adjacent_seats(
+                position,
+                seating_arrangement,
+                adjacency_definition,
+            )) {
+                0 => FloorTile::Seat { occupied: true },
+                _ => FloorTile::Seat { occupied: false },
+            }
+        }
+        FloorTile::Floor => FloorTile::Floor,
+    })
 }
 
-fn count_occupided_seats(seats: Vec<FloorTile>) -> usize {
+pub fn count_occupided_seats<I>(seats: I) -> usize
+where
+    I: IntoIterator<Item = FloorTile>,
+{
     seats
         .into_iter()
-        .filter(|tile| match tile {
-            FloorTile::Seat { occupied: true } => true,
-            _ => false,
-        })
+        .filter(|tile| matches!(tile, FloorTile::Seat { occupied: true }))
         .count()
 }
 
-fn get_line_of_sight_seats(
-    x: &usize,
-    y: &usize,
-    seating_arrangement: &Vec<Vec<FloorTile>>,
+fn get_adjacent_seats(
+    position: Position,
+    seating_arrangement: &Grid<FloorTile>,
+    adjacency_definition: &AdjacencyDefinition,
 ) -> Vec<FloorTile> {
-    let mut result = Vec::new();
-    let y_max = seating_arrangement.len() - 1;
-    let x_max = seating_arrangement[0].len() - 1;
-
-    // up left
-    traverse_until_seat(
-        x,
-        y,
-        seating_arrangement,
-        Option::Some(0),
-        Option::Some(0),
-        &std::ops::Sub::sub,
-        &std::ops::Sub::sub,
-    )
-    .iter()
-    .for_each(|tile| result.push(*tile));
-
-    // up
-    traverse_until_seat(
-        x,
-        y,
-        seating_arrangement,
-        Option::None,
-        Option::Some(0),
-        &std::ops::Mul::mul,
-        &std::ops::Sub::sub,
-    )
-    .iter()
-    .for_each(|tile| result.push(*tile));
-
-    // up right
-    traverse_until_seat(
-        x,
-        y,
-        seating_arrangement,
-        Option::Some(x_max),
-        Option::Some(0),
-        &std::ops::Add::add,
-        &std::ops::Sub::sub,
-    )
-    .iter()
-    .for_each(|tile| result.push(*tile));
-
-    // left
-    traverse_until_seat(
-        x,
-        y,
-        seating_arrangement,
-        Option::Some(0),
-        Option::None,
-        &std::ops::Sub::sub,
-        &std::ops::Mul::mul,
-    )
-    .iter()
-    .for_each(|tile| result.push(*tile));
-
-    // right
-    traverse_until_seat(
-        x,
-        y,
-        seating_arrangement,
-        Option::Some(x_max),
-        Option::None,
-        &std::ops::Add::add,
-        &std::ops::Mul::mul,
-    )
-    .iter()
-    .for_each(|tile| result.push(*tile));
-
-    // left down
-    traverse_until_seat(
-        x,
-        y,
-        seating_arrangement,
-        Option::Some(0),
-        Option::Some(y_max),
-        &std::ops::Sub::sub,
-        &std::ops::Add::add,
-    )
-    .iter()
-    .for_each(|tile| result.push(*tile));
-
-    // down
-    traverse_until_seat(
-        x,
-        y,
-        seating_arrangement,
-        Option::None,
-        Option::Some(y_max),
-        &std::ops::Mul::mul,
-        &std::ops::Add::add,
-    )
-    .iter()
-    .for_each(|tile| result.push(*tile));
-
-    // down right
-    traverse_until_seat(
-        x,
-        y,
-        seating_arrangement,
-        Option::Some(x_max),
-        Option::Some(y_max),
-        &std::ops::Add::add,
-        &std::ops::Add::add,
-    )
-    .iter()
-    .for_each(|tile| result.push(*tile));
-
-    result
+    match adjacency_definition {
+        AdjacencyDefinition::DirectlyNextTo => seating_arrangement
+            .neighbours(position, Neighbourhood::Eight, Wrap::None)
+            .map(|(_, tile)| *tile)
+            .collect(),
+        AdjacencyDefinition::LineOfSight => Neighbourhood::Eight
+            .offsets()
+            .iter()
+            .filter_map(|direction| {
+                seating_arrangement
+                    .ray(position, *direction, Wrap::None)
+                    .map(|(_, tile)| *tile)
+                    .find(|tile| matches!(tile, FloorTile::Seat { .. }))
+            })
+            .collect(),
+    }
 }
 
-fn traverse_until_seat(
-    x: &usize,
-    y: &usize,
-    seating_arrangement: &Vec<Vec<FloorTile>>,
-    x_stop: Option<usize>,
-    y_stop: Option<usize>,
-    x_move: &dyn Fn(usize, usize) -> usize,
-    y_move: &dyn Fn(usize, usize) -> usize,
-) -> Option<FloorTile> {
-    let mut current_x = *x;
-    let mut current_y = *y;
-
-    loop {
-        if x_stop.map(|stop| current_x == stop).unwrap_or(false)
-            || y_stop.map(|stop| current_y == stop).unwrap_or(false)
-        {
-            break;
-        }
-
-        current_x = x_move(current_x, 1);
-        current_y = y_move(current_y, 1);
+pub fn parse_input(input: &mut InputReader) -> Result<Grid<FloorTile>, Error> {
+    Grid::parse(
+        input,
+        |character| match character {
+            '.' => Some(FloorTile::Floor),
+            '#' => Some(FloorTile::Seat { occupied: true }),
+            'L' => Some(FloorTile::Seat { occupied: false }),
+            _ => None,
+        },
+        "a row of '.', '#' or 'L'",
+    )
+}
 
-        match seating_arrangement[current_y][current_x] {
-            FloorTile::Seat { occupied: _ } => {
-                return Option::Some(seating_arrangement[current_y][current_x]);
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    let mut seats = Grid::from_rows(
+        (0..options.size)
+            .map(|_| {
+                (0..options.width)
+                    .map(|_| {
+                        if rng.gen_bool(0.15) {
+                            FloorTile::Floor
+                        } else {
+                            FloorTile::Seat { occupied: false }
+                        }
+                    })
+                    .collect()
+            })
+            .collect(),
+    )?;
+
+    // Random layouts can flip back and forth forever, some of the seats that never settle become
+    // floor until both presets reach an equilibrium
+    let presets = iter::once(SeatingSystem.part1_args(String::new()))
+        .chain(SeatingSystem.part2_args(String::new()))
+        .collect::<Vec<SeatingSystemArgs>>();
+    while let Some(unsettled) = presets.iter().find_map(|arguments| {
+        find_unsettled_seats(
+            &seats,
+            &arguments.tolerance,
+            &arguments.adjacency_definition,
+        )
+    }) {
+        let removed = unsettled
+            .choose_multiple(rng, (unsettled.len() / 10).max(1))
+            .copied()
+            .collect::<HashSet<Position>>();
+        seats = seats.map(|position, tile| {
+            if removed.contains(&position) {
+                FloorTile::Floor
+            } else {
+                *tile
             }
-            FloorTile::Floor => (),
-        }
+        });
     }
 
-    Option::None
+    write!(output, "{}", seats)?;
+    Ok(())
 }
 
-fn get_adjacent_tiles(
-    x: &usize,
-    y: &usize,
-    seating_arrangement: &Vec<Vec<FloorTile>>,
-) -> Vec<FloorTile> {
-    let mut result = Vec::new();
-    if y > &0 {
-        result.extend(find_adjacent_tiles_in_row(
-            x,
-            &seating_arrangement[y - 1],
-            true,
-        ));
-    }
+/// Seats that keep flipping once the arrangement stops converging, or None if it reaches an
+/// equilibrium. Rules like these only ever settle or alternate between two arrangements.
+fn find_unsettled_seats(
+    seating_arrangement: &Grid<FloorTile>,
+    tolerance: &usize,
+    adjacency_definition: &AdjacencyDefinition,
+) -> Option<Vec<Position>> {
+    let mut previous_arrangement = seating_arrangement.clone();
+    let mut arrangement = iterate_seats(&previous_arrangement, tolerance, adjacency_definition);
+    while arrangement != previous_arrangement {
+        let next_arrangement = iterate_seats(&arrangement, tolerance, adjacency_definition);
+        if next_arrangement == previous_arrangement {
+            return Some(
+                arrangement
+                    .iter()
+                    .zip(next_arrangement.values())
+                    .filter(|((_, tile), next_tile)| tile != next_tile)
+                    .map(|((position, _), _)| position)
+                    .collect(),
+            );
+        }
 
-    result.extend(find_adjacent_tiles_in_row(
-        x,
-        &seating_arrangement[*y],
-        false,
-    ));
-
-    if *y < seating_arrangement.len() - 1 {
-        result.extend(find_adjacent_tiles_in_row(
-            x,
-            &seating_arrangement[y + 1],
-            true,
-        ));
+        previous_arrangement = arrangement;
+        arrangement = next_arrangement;
     }
 
-    result
+    None
 }
 
-fn find_adjacent_tiles_in_row(x: &usize, row: &Vec<FloorTile>, include_x: bool) -> Vec<FloorTile> {
-    let mut result = Vec::new();
-    if x > &0 {
-        result.push(row[x - 1]);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generated_input, sample_input};
+
+    fn occupied(seating_arrangement: &Grid<FloorTile>) -> usize {
+        count_occupided_seats(seating_arrangement.values().copied())
     }
 
-    if include_x {
-        result.push(row[*x]);
+    #[test]
+    fn parses_seats() {
+        let seating_arrangement = parse_input(&mut InputReader::from_text("L.#\n")).unwrap();
+
+        assert_eq!(seating_arrangement.to_string(), "L.#\n");
+        assert!(parse_input(&mut InputReader::from_text("L.x\n")).is_err());
     }
 
-    if *x < row.len() - 1 {
-        result.push(row[x + 1]);
+    #[test]
+    fn everyone_sits_down_in_the_first_round() {
+        let seating_arrangement = parse_input(&mut sample_input(11, "sample")).unwrap();
+        let next = iterate_seats(
+            &seating_arrangement,
+            &4,
+            &AdjacencyDefinition::DirectlyNextTo,
+        );
+
+        assert_eq!(occupied(&seating_arrangement), 0);
+        assert_eq!(
+            occupied(&next),
+            seating_arrangement
+                .values()
+                .filter(|tile| matches!(tile, FloorTile::Seat { .. }))
+                .count()
+        );
     }
 
-    result
-}
+    #[test]
+    fn solves_sample() {
+        let seating_arrangement = parse_input(&mut sample_input(11, "sample")).unwrap();
 
-fn parse_row_of_seats(line: &String) -> Result<Vec<FloorTile>, Error> {
-    many1(alt((
-        map(complete::char('.'), |_| FloorTile::Floor),
-        map(complete::char('#'), |_| FloorTile::Seat { occupied: true }),
-        map(complete::char('L'), |_| FloorTile::Seat { occupied: false }),
-    )))(line.as_str())
-    .map(|(_, seating_arrangement)| seating_arrangement)
-    .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse failure").into())
+        assert_eq!(
+            occupied(&find_equalibrium(
+                &seating_arrangement,
+                &4,
+                &AdjacencyDefinition::DirectlyNextTo
+            )),
+            37
+        );
+        assert_eq!(
+            occupied(&find_equalibrium(
+                &seating_arrangement,
+                &5,
+                &AdjacencyDefinition::LineOfSight
+            )),
+            26
+        );
+    }
+
+    #[test]
+    fn generated_seating_reaches_equilibrium() {
+        let seating_arrangement = parse_input(&mut InputReader::from_text(&generated_input(
+            generate_input,
+            60,
+            7,
+        )))
+        .unwrap();
+
+        assert_eq!(seating_arrangement.width(), 60);
+        assert!(
+            occupied(&find_equalibrium(
+                &seating_arrangement,
+                &4,
+                &AdjacencyDefinition::DirectlyNextTo
+            )) > 0
+        );
+        assert!(
+            occupied(&find_equalibrium(
+                &seating_arrangement,
+                &5,
+                &AdjacencyDefinition::LineOfSight
+            )) > 0
+        );
+    }
 }
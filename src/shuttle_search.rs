@@ -1,79 +1,113 @@
-use crate::lib::{default_sub_command, file_to_string, parse_isize, Command};
+use crate::{
+    default_sub_command, parse_isize, preset_arg, value_or_preset, GenerateOptions, InputReader,
+    ParseError, Puzzle,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, App, ArgMatches, SubCommand};
+use clap::{App, ArgMatches};
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    combinator::map,
+    combinator::{map, verify},
     multi::separated_list1,
     sequence::{terminated, tuple},
 };
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
 use simple_error::SimpleError;
+use std::io::Write;
+use std::iter;
 
-pub const SHUTTLE_SEARCH: Command = Command::new(sub_command, "shuttle-search", run);
+pub struct ShuttleSearch;
 
-#[derive(Debug)]
-struct ShuttleSearchArgs {
-    file: String,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShuttleSearchArgs {
+    pub file: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum BusRoute {
+pub enum BusRoute {
     Bus(isize),
     // I'm *sure* this will come up later
     X,
 }
 
 #[derive(Debug, Clone)]
-struct BusSchedule {
-    depart_time: isize,
-    routes: Vec<BusRoute>,
+pub struct BusSchedule {
+    pub depart_time: isize,
+    pub routes: Vec<BusRoute>,
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(
-        &SHUTTLE_SEARCH,
-        "Takes a file with a target time and bus schedule then finds the next bus and multiplies that by \
-        the wait time.",
-        "Path to the input file. First line contains the target time. Next line contains the comma \
-        delimited bus schedule.",
-    )
-    .subcommand(
-        SubCommand::with_name("part1")
-            .about(
-                "Finds the next bus information with the default input.",
-            )
-            .version("1.0.0"),
-    )
-}
+impl Puzzle for ShuttleSearch {
+    type Args = ShuttleSearchArgs;
+    type Input = BusSchedule;
+    type Answer = isize;
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let shuttle_search_arguments = match arguments.subcommand_name() {
-        Some("part1") => ShuttleSearchArgs {
-            file: "day13/input.txt".to_string(),
-        },
-        _ => ShuttleSearchArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-        },
-    };
+    const NAME: &'static str = "shuttle-search";
+    const DAY: usize = 13;
+    const GENERATE_ABOUT: &'static str =
+        "Writes a schedule with size buses. Bus ids are distinct primes so they are all coprime, \
+        with a few out of service buses between them.";
 
-    process_schedule(&shuttle_search_arguments)
-        .map(|result| {
-            println!("{:#?}", result);
-        })
-        .map(|_| ())
-}
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(
+            Self::NAME,
+            "Takes a file with a target time and bus schedule then finds the next bus and multiplies that by \
+            the wait time.",
+            "Path to the input file. First line contains the target time. Next line contains the comma \
+            delimited bus schedule.",
+        )
+        .arg(preset_arg(&["part1"]).help(
+            "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+            part1: Finds the next bus information with the default input.\n",
+        ))
+    }
 
-fn process_schedule(shuttle_search_arguments: &ShuttleSearchArgs) -> Result<isize, Error> {
-    file_to_string(&shuttle_search_arguments.file)
-        .and_then(|file| parse_schedule(&file))
-        .map(|schedule| {
-            let (bus_number, depart_time) = find_next_bus(&schedule);
-            (depart_time - schedule.depart_time) * bus_number
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&ShuttleSearchArgs>,
+    ) -> Result<ShuttleSearchArgs, Error> {
+        Ok(ShuttleSearchArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
         })
+    }
+
+    fn part1_args(&self, file: String) -> ShuttleSearchArgs {
+        ShuttleSearchArgs { file }
+    }
+
+    fn file<'a>(&self, arguments: &'a ShuttleSearchArgs) -> &'a str {
+        &arguments.file
+    }
+
+    fn with_file(&self, _arguments: ShuttleSearchArgs, file: String) -> ShuttleSearchArgs {
+        ShuttleSearchArgs { file }
+    }
+
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &ShuttleSearchArgs,
+    ) -> Result<BusSchedule, Error> {
+        parse_input(input)
+    }
+
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
+
+    fn solve(&self, schedule: BusSchedule, _arguments: &ShuttleSearchArgs) -> Result<isize, Error> {
+        let (bus_number, depart_time) = find_next_bus(&schedule)?;
+        Ok((depart_time - schedule.depart_time) * bus_number)
+    }
 }
 
-fn find_next_bus(schedule: &BusSchedule) -> (isize, isize) {
+pub fn find_next_bus(schedule: &BusSchedule) -> Result<(isize, isize), Error> {
     schedule
         .routes
         .clone()
@@ -87,11 +121,15 @@ fn find_next_bus(schedule: &BusSchedule) -> (isize, isize) {
                 schedule.depart_time - ((schedule.depart_time % bus_number) - bus_number);
             (bus_number, depart_time)
         })
-        .fold_first(|low, new| if new.1 < low.1 { new } else { low })
-        .unwrap()
+        .reduce(|low, new| if new.1 < low.1 { new } else { low })
+        .ok_or_else(|| SimpleError::new("No buses are in service").into())
 }
 
-fn parse_schedule(file: &String) -> Result<BusSchedule, Error> {
+pub fn parse_input(input: &mut InputReader) -> Result<BusSchedule, Error> {
+    input.parse_text(parse_schedule)
+}
+
+fn parse_schedule(input: &str) -> Result<BusSchedule, Error> {
     map(
         tuple((
             terminated(parse_isize, tag("\n")),
@@ -99,7 +137,7 @@ fn parse_schedule(file: &String) -> Result<BusSchedule, Error> {
                 separated_list1(
                     tag(","),
                     alt((
-                        map(parse_isize, |bus_number| BusRoute::Bus(bus_number)),
+                        map(verify(parse_isize, |id| *id > 0), BusRoute::Bus),
                         map(tag("x"), |_| BusRoute::X),
                     )),
                 ),
@@ -107,10 +145,106 @@ fn parse_schedule(file: &String) -> Result<BusSchedule, Error> {
             ),
         )),
         |(depart_time, routes)| BusSchedule {
-            depart_time: depart_time,
-            routes: routes,
+            depart_time,
+            routes,
         },
-    )(file)
-    .map_err(|_| SimpleError::new("Parse failure").into())
+    )(input)
+    .map_err(|err: nom::Err<nom::error::Error<&str>>| {
+        ParseError::from_nom(
+            input,
+            err,
+            "a depart time line followed by a line of comma separated positive bus ids or x",
+        )
+        .into()
+    })
     .map(|(_, bus_schedule)| bus_schedule)
 }
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    options.require_size(1, usize::MAX)?;
+    let primes = iter::successors(Some(64), |limit| Some(limit * 2))
+        .map(primes_from_seven_below)
+        .find(|primes| primes.len() >= options.size * 2)
+        .unwrap_or_default();
+    let ids = primes
+        .choose_multiple(rng, options.size)
+        .copied()
+        .collect::<Vec<isize>>();
+
+    let routes = ids
+        .iter()
+        .enumerate()
+        .flat_map(|(index, id)| {
+            let out_of_service = if index == 0 { 0 } else { rng.gen_range(0..=2) };
+            iter::repeat_n("x".to_string(), out_of_service)
+                .chain(iter::once(id.to_string()))
+                .collect::<Vec<String>>()
+        })
+        .collect::<Vec<String>>();
+
+    writeln!(output, "{}", rng.gen_range(1000..=1_000_000))?;
+    writeln!(output, "{}", routes.join(","))?;
+    Ok(())
+}
+
+fn primes_from_seven_below(limit: usize) -> Vec<isize> {
+    let mut composite = vec![false; limit];
+    (2..limit)
+        .filter(|number| {
+            if composite[*number] {
+                return false;
+            }
+            (number * number..limit)
+                .step_by(*number)
+                .for_each(|multiple| composite[multiple] = true);
+            true
+        })
+        .filter(|prime| *prime >= 7)
+        .map(|prime| prime as isize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_input;
+
+    #[test]
+    fn parses_schedule() {
+        let schedule = parse_schedule("939\n7,13,x,59\n").unwrap();
+
+        assert_eq!(schedule.depart_time, 939);
+        assert_eq!(
+            schedule.routes,
+            vec![
+                BusRoute::Bus(7),
+                BusRoute::Bus(13),
+                BusRoute::X,
+                BusRoute::Bus(59)
+            ]
+        );
+        assert!(parse_schedule("939\n7,y\n").is_err());
+        assert!(parse_schedule("939\n7,0\n").is_err());
+    }
+
+    #[test]
+    fn finds_next_bus_in_sample() {
+        let schedule = parse_input(&mut sample_input(13, "sample")).unwrap();
+
+        assert_eq!(find_next_bus(&schedule).unwrap(), (59, 944));
+    }
+
+    #[test]
+    fn fails_when_no_bus_is_in_service() {
+        let schedule = parse_schedule("939\nx,x\n").unwrap();
+
+        assert_eq!(
+            find_next_bus(&schedule).unwrap_err().to_string(),
+            "No buses are in service"
+        );
+    }
+}
@@ -1,82 +1,124 @@
-use crate::lib::{default_sub_command, file_to_lines, parse_lines, parse_usize, Command};
+use crate::grid::{Grid, Wrap};
+use crate::{
+    default_sub_command, parse_usize, preset_arg, value_or_preset, values_or_preset,
+    GenerateOptions, InputReader, ParseError, Puzzle,
+};
 use anyhow::Error;
-use clap::{value_t_or_exit, values_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches};
 use nom::{
-    branch::alt,
     character::complete,
-    combinator::map,
-    multi::many1,
+    combinator::all_consuming,
     sequence::{preceded, tuple},
 };
-use simple_error::SimpleError;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
 use std::str::FromStr;
 
-pub const TOBOGGAN_TRAJECTORY: Command = Command::new(sub_command, "toboggan-trajectory", run);
+pub struct TobogganTrajectory;
 
-struct TobogganTrajectoryArgs {
-    file: String,
-    slopes: Vec<Slope>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TobogganTrajectoryArgs {
+    pub file: String,
+    pub slopes: Vec<Slope>,
 }
 
-struct Slope {
-    right: usize,
-    down: usize,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Slope {
+    pub right: usize,
+    pub down: usize,
 }
 
 impl FromStr for Slope {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        tuple((parse_usize, preceded(complete::char(','), parse_usize)))(s)
-            .map(|(_, (right, down))| Slope {
-                right: right,
-                down: down,
-            })
-            .map_err(|_| SimpleError::new("Parse failure").into())
+        all_consuming(tuple((
+            parse_usize,
+            preceded(complete::char(','), parse_usize),
+        )))(s)
+        .map(|(_, (right, down))| Slope { right, down })
+        .map_err(|err| ParseError::from_nom(s, err, "a slope as right,down such as 3,1").into())
     }
 }
 
-#[derive(Debug)]
-enum Terrain {
+#[derive(Debug, Clone)]
+pub enum Terrain {
     Clear,
     Tree,
 }
 
-fn sub_command() -> App<'static, 'static> {
-    default_sub_command(&TOBOGGAN_TRAJECTORY, "Takes a toboggan hill and a slope an returns the product of the number of trees \
-    that the toboggan hit on each slope", "Path to the input file. Input should be a toboggan hill with . denoting\
-    an empty space and # denoting a tree.")
-        .arg(Arg::with_name("slope")
-            .short("s")
-            .help(
-                    "Slope of the toboggan specified by number of right units then number of down units \
-                separated by a comma. Example: 3,1",
-                )
-                .takes_value(true)
-                .multiple(true)
-                .number_of_values(1)
-                .min_values(1),
-        )
-        .subcommand(
-            SubCommand::with_name("part1")
-                .about("Validates the default input with a single slope of 3,1")
-                .version("1.0.0"),
-        )
-        .subcommand(
-            SubCommand::with_name("part2")
-                .about("Validates the default input with slopes of 1,1 3,1 5,1 7,1 1,2")
-                .version("1.0.0"),
+impl fmt::Display for Terrain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Terrain::Clear => '.',
+                Terrain::Tree => '#',
+            }
         )
+    }
 }
 
-fn run(arguments: &ArgMatches) -> Result<(), Error> {
-    let tobaggan_tarjectory_arguments = match arguments.subcommand_name() {
-        Some("part1") => TobogganTrajectoryArgs {
-            file: "day3/input.txt".to_string(),
+impl Puzzle for TobogganTrajectory {
+    type Args = TobogganTrajectoryArgs;
+    type Input = Grid<Terrain>;
+    type Answer = usize;
+
+    const NAME: &'static str = "toboggan-trajectory";
+    const DAY: usize = 3;
+    const GENERATE_ABOUT: &'static str =
+        "Writes a hill size rows tall and width columns wide where about a fifth of the tiles are trees.";
+
+    fn sub_command(&self) -> App<'static, 'static> {
+        default_sub_command(Self::NAME, "Takes a toboggan hill and a slope an returns the product of the number of trees \
+        that the toboggan hit on each slope", "Path to the input file. Input should be a toboggan hill with . denoting\
+        an empty space and # denoting a tree.")
+            .arg(Arg::with_name("slope")
+                .short("s")
+                .help(
+                        "Slope of the toboggan specified by number of right units then number of down units \
+                    separated by a comma. Example: 3,1",
+                    )
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .min_values(1),
+            )
+            .arg(preset_arg(&["part1", "part2"]).help(
+                "The puzzle part to take the arguments from. The presets are as follows:\n\n\
+                part1: Validates the default input with a single slope of 3,1\n\n\
+                part2: Validates the default input with slopes of 1,1 3,1 5,1 7,1 1,2\n",
+            ))
+    }
+
+    fn parse_arguments(
+        &self,
+        arguments: &ArgMatches,
+        preset: Option<&TobogganTrajectoryArgs>,
+    ) -> Result<TobogganTrajectoryArgs, Error> {
+        Ok(TobogganTrajectoryArgs {
+            file: value_or_preset(arguments, "file", preset.map(|preset| &preset.file))?,
+            slopes: values_or_preset(
+                arguments,
+                "slope",
+                preset.map(|preset| preset.slopes.as_slice()),
+            )?,
+        })
+    }
+
+    fn part1_args(&self, file: String) -> TobogganTrajectoryArgs {
+        TobogganTrajectoryArgs {
+            file,
             slopes: vec![Slope { right: 3, down: 1 }],
-        },
-        Some("part2") => TobogganTrajectoryArgs {
-            file: "day3/input.txt".to_string(),
+        }
+    }
+
+    fn part2_args(&self, file: String) -> Option<TobogganTrajectoryArgs> {
+        Some(TobogganTrajectoryArgs {
+            file,
             slopes: vec![
                 Slope { right: 1, down: 1 },
                 Slope { right: 3, down: 1 },
@@ -84,56 +126,131 @@ fn run(arguments: &ArgMatches) -> Result<(), Error> {
                 Slope { right: 7, down: 1 },
                 Slope { right: 1, down: 2 },
             ],
-        },
-        _ => TobogganTrajectoryArgs {
-            file: value_t_or_exit!(arguments.value_of("file"), String),
-            slopes: values_t_or_exit!(arguments.values_of("slope"), Slope),
-        },
-    };
-
-    file_to_lines(&tobaggan_tarjectory_arguments.file)
-        .and_then(|lines| parse_lines(lines, parse_toboggan_line))
-        .map(|hill| {
-            tobaggan_tarjectory_arguments
-                .slopes
-                .into_iter()
-                .map(|slope| run_through_slope(&hill, &slope))
-                .fold(1usize, |acc, trees| acc * trees)
-        })
-        .map(|result| {
-            println!("{:#?}", result);
         })
-        .map(|_| ())
-}
+    }
+
+    fn file<'a>(&self, arguments: &'a TobogganTrajectoryArgs) -> &'a str {
+        &arguments.file
+    }
 
-fn run_through_slope(hill: &Vec<Vec<Terrain>>, slope: &Slope) -> usize {
-    let x_max = hill[0].len();
-    let mut x = 0;
-    let mut y = 0;
-    let mut tree_count = 0;
+    fn with_file(&self, arguments: TobogganTrajectoryArgs, file: String) -> TobogganTrajectoryArgs {
+        TobogganTrajectoryArgs { file, ..arguments }
+    }
 
-    loop {
-        x = (x + slope.right) % x_max;
-        y = y + slope.down;
+    fn parse(
+        &self,
+        input: &mut InputReader,
+        _arguments: &TobogganTrajectoryArgs,
+    ) -> Result<Grid<Terrain>, Error> {
+        parse_input(input)
+    }
 
-        if y >= hill.len() {
-            break;
-        }
+    fn generate(
+        &self,
+        options: &GenerateOptions,
+        rng: &mut dyn RngCore,
+        output: &mut dyn Write,
+    ) -> Result<(), Error> {
+        generate_input(options, rng, output)
+    }
 
-        tree_count += match hill[y][x] {
-            Terrain::Clear => 0,
-            Terrain::Tree => 1,
-        };
+    fn display_input(&self, hill: &Grid<Terrain>) -> Option<String> {
+        Some(hill.to_string())
     }
 
-    tree_count
+    fn solve(
+        &self,
+        hill: Grid<Terrain>,
+        arguments: &TobogganTrajectoryArgs,
+    ) -> Result<usize, Error> {
+        Ok(arguments
+            .slopes
+            .iter()
+            .map(|slope| run_through_slope(&hill, slope))
+            .product())
+    }
+}
+
+pub fn run_through_slope(hill: &Grid<Terrain>, slope: &Slope) -> usize {
+    hill.ray(
+        (0, 0),
+        (slope.right as isize, slope.down as isize),
+        Wrap::Horizontal,
+    )
+    .filter(|(_, terrain)| matches!(terrain, Terrain::Tree))
+    .count()
+}
+
+pub fn parse_input(input: &mut InputReader) -> Result<Grid<Terrain>, Error> {
+    Grid::parse(
+        input,
+        |character| match character {
+            '.' => Some(Terrain::Clear),
+            '#' => Some(Terrain::Tree),
+            _ => None,
+        },
+        "a row of '.' or '#'",
+    )
+}
+
+pub fn generate_input(
+    options: &GenerateOptions,
+    rng: &mut dyn RngCore,
+    output: &mut dyn Write,
+) -> Result<(), Error> {
+    let hill = Grid::from_rows(
+        (0..options.size)
+            .map(|_| {
+                (0..options.width)
+                    .map(|_| {
+                        if rng.gen_bool(0.2) {
+                            Terrain::Tree
+                        } else {
+                            Terrain::Clear
+                        }
+                    })
+                    .collect()
+            })
+            .collect(),
+    )?;
+    write!(output, "{}", hill)?;
+    Ok(())
 }
 
-fn parse_toboggan_line(line: &String) -> Result<Vec<Terrain>, Error> {
-    many1(alt((
-        map(complete::char('.'), |_| Terrain::Clear),
-        map(complete::char('#'), |_| Terrain::Tree),
-    )))(line.as_str())
-    .map(|(_, terrain)| terrain)
-    .map_err(|_: nom::Err<nom::error::Error<&str>>| SimpleError::new("Parse failure").into())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_input;
+
+    #[test]
+    fn parses_slope() {
+        let slope = "3,1".parse::<Slope>().unwrap();
+
+        assert_eq!((slope.right, slope.down), (3, 1));
+        assert!("3".parse::<Slope>().is_err());
+        assert_eq!(
+            "3x1".parse::<Slope>().unwrap_err().to_string(),
+            "<input>:1:2: expected a slope as right,down such as 3,1, found \"3x1\""
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_terrain() {
+        assert!(parse_input(&mut InputReader::from_text("..#\n.x.\n")).is_err());
+    }
+
+    #[test]
+    fn solves_sample() {
+        let hill = parse_input(&mut sample_input(3, "sample")).unwrap();
+        let slopes = TobogganTrajectory.part2_args(String::new()).unwrap().slopes;
+
+        assert_eq!(run_through_slope(&hill, &Slope { right: 3, down: 1 }), 7);
+        assert_eq!(
+            slopes
+                .iter()
+                .map(|slope| run_through_slope(&hill, slope))
+                .product::<usize>(),
+            336
+        );
+    }
 }